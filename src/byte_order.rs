@@ -0,0 +1,179 @@
+//! Conversion of little-endian caches to the big-endian byte order [IconCache] reads.
+
+use crate::{IconCache, IconCacheError, raw};
+use alloc::collections::BTreeSet;
+
+impl IconCache<'_> {
+    /// Returns `true` if `bytes` start with the header of a little-endian cache.
+    ///
+    /// `gtk-update-icon-cache` writes caches in big-endian (network) byte order, which is the only
+    /// byte order [new_from_bytes](Self::new_from_bytes) accepts. Little-endian caches are
+    /// recognized by their major version, `1`, reading as `0x0100`.
+    pub fn is_byte_swapped(bytes: &[u8]) -> bool {
+        bytes.starts_with(&[1, 0])
+    }
+
+    /// Swap the byte order of every integer in the cache in-place, converting a little-endian
+    /// cache to a big-endian one or vice versa. The byte order is detected from the header.
+    ///
+    /// Strings and embedded pixel data are left as-is. Structures that can't be reached, e.g.
+    /// because an offset points outside of the cache, are skipped, like [IconCache] skips them when
+    /// reading.
+    ///
+    /// Returns an error if the header, hash table or directory list could not be read, in which
+    /// case `bytes` may have been partially converted.
+    pub fn swap_byte_order(bytes: &mut [u8]) -> Result<(), IconCacheError> {
+        let to_little_endian = !Self::is_byte_swapped(bytes);
+        let mut swapper = Swapper {
+            bytes,
+            to_little_endian,
+            swapped: BTreeSet::new(),
+        };
+
+        swapper.cache().ok_or(IconCacheError::Truncated)
+    }
+}
+
+struct Swapper<'b> {
+    bytes: &'b mut [u8],
+    to_little_endian: bool,
+    /// Offsets of the fields that have been swapped already, so that structures referenced more
+    /// than once are only swapped once
+    swapped: BTreeSet<usize>,
+}
+
+impl Swapper<'_> {
+    fn cache(&mut self) -> Option<()> {
+        self.u16(0)?;
+        self.u16(2)?;
+        let hash = self.u32(4)?;
+        let directory_list = self.u32(8)?;
+
+        let n_buckets = self.u32(hash)?;
+        let n_directories = self.u32(directory_list)?;
+
+        for idx in 0..n_directories {
+            self.u32(directory_list + 4 + 4 * idx)?;
+        }
+
+        for idx in 0..n_buckets {
+            let mut icon = self.u32(hash + 4 + 4 * idx)?;
+
+            // Checking `swapped` also guards against chains that loop back onto themselves
+            while !is_null(icon) && !self.swapped.contains(&icon) {
+                let Some(next) = self.icon(icon) else {
+                    break;
+                };
+                icon = next;
+            }
+        }
+
+        Some(())
+    }
+
+    /// Swap an icon, returning the offset of the next icon in its chain.
+    fn icon(&mut self, icon: usize) -> Option<usize> {
+        let chain = self.u32(icon)?;
+        self.u32(icon + 4)?;
+        let image_list = self.u32(icon + 8)?;
+
+        self.image_list(image_list);
+
+        Some(chain)
+    }
+
+    fn image_list(&mut self, image_list: usize) -> Option<()> {
+        let n_images = self.u32(image_list)?;
+
+        for idx in 0..n_images {
+            let image = image_list + 4 + size_of::<raw::Image>() * idx;
+            self.u16(image)?;
+            self.u16(image + 2)?;
+            let image_data = self.u32(image + 4)?;
+
+            if image_data != 0 {
+                self.image_data(image_data);
+            }
+        }
+
+        Some(())
+    }
+
+    fn image_data(&mut self, image_data: usize) -> Option<()> {
+        self.u32(image_data)?;
+        let meta_data = self.u32(image_data + 4)?;
+        self.u32(image_data + 8)?;
+        self.u32(image_data + 12)?;
+
+        if is_null(meta_data) {
+            return Some(());
+        }
+
+        let embedded_rect = self.u32(meta_data)?;
+        let attach_point_list = self.u32(meta_data + 4)?;
+        let display_name_list = self.u32(meta_data + 8)?;
+
+        if !is_null(embedded_rect) {
+            for field in 0..4 {
+                self.u16(embedded_rect + 2 * field)?;
+            }
+        }
+
+        if !is_null(attach_point_list) {
+            let n_attach_points = self.u32(attach_point_list)?;
+            for idx in 0..2 * n_attach_points {
+                self.u16(attach_point_list + 4 + 2 * idx)?;
+            }
+        }
+
+        if !is_null(display_name_list) {
+            let n_display_names = self.u32(display_name_list)?;
+            for idx in 0..2 * n_display_names {
+                self.u32(display_name_list + 4 + 4 * idx)?;
+            }
+        }
+
+        Some(())
+    }
+
+    /// Swap the field at `offset` if it hasn't been already, returning it in the new byte order.
+    fn field<const N: usize>(&mut self, offset: usize) -> Option<[u8; N]> {
+        let field: &mut [u8; N] = self
+            .bytes
+            .get_mut(offset..offset.checked_add(N)?)?
+            .try_into()
+            .ok()?;
+
+        if self.swapped.insert(offset) {
+            field.reverse();
+        }
+
+        Some(*field)
+    }
+
+    fn u16(&mut self, offset: usize) -> Option<u16> {
+        let field = self.field(offset)?;
+
+        Some(if self.to_little_endian {
+            u16::from_le_bytes(field)
+        } else {
+            u16::from_be_bytes(field)
+        })
+    }
+
+    fn u32(&mut self, offset: usize) -> Option<usize> {
+        let field = self.field(offset)?;
+
+        let value = if self.to_little_endian {
+            u32::from_le_bytes(field)
+        } else {
+            u32::from_be_bytes(field)
+        };
+
+        Some(value as usize)
+    }
+}
+
+fn is_null(offset: usize) -> bool {
+    offset == 0 || offset == 0xFFFFFFFF
+}
@@ -0,0 +1,157 @@
+//! Look up icons across several caches, e.g. an icon theme and the themes it inherits from.
+
+#[cfg(feature = "std")]
+use crate::Image;
+use crate::{Icon, IconCache};
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use core::ffi::CStr;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+/// An ordered list of icon caches, consulted one after another.
+///
+/// Icon themes inherit from parent themes (e.g. `Adwaita` from `hicolor`): an icon missing from a
+/// theme is looked up in its parents. Put the caches in the order they should be consulted, i.e.
+/// the theme itself first, followed by its parents.
+#[derive(Debug, Clone, Default)]
+pub struct IconCacheChain<'a> {
+    pub caches: Vec<IconCache<'a>>,
+}
+
+impl<'a> IconCacheChain<'a> {
+    pub fn new(caches: Vec<IconCache<'a>>) -> Self {
+        Self { caches }
+    }
+
+    /// Look up an icon by name in each cache in order, returning the first match.
+    pub fn icon(&self, icon_name: impl AsRef<[u8]>) -> Option<Icon<'a>> {
+        let icon_name = icon_name.as_ref();
+
+        self.caches.iter().find_map(|cache| cache.icon(icon_name))
+    }
+
+    /// Returns `true` if any of the caches contains an icon named `icon_name`.
+    ///
+    /// See [IconCache::contains].
+    pub fn contains(&self, icon_name: impl AsRef<[u8]>) -> bool {
+        let icon_name = icon_name.as_ref();
+
+        self.caches.iter().any(|cache| cache.contains(icon_name))
+    }
+
+    /// Returns an iterator over the images of `icon_name` in every cache that has it, rather than
+    /// just the first, e.g. to pick the best size when a parent theme provides a size the theme
+    /// itself doesn't.
+    ///
+    /// Images in a directory already seen in an earlier cache are skipped: the earlier cache's
+    /// image takes precedence.
+    #[cfg(feature = "std")]
+    pub fn all_images_for(&self, icon_name: impl AsRef<[u8]>) -> impl Iterator<Item = Image<'a>> {
+        let icon_name = icon_name.as_ref();
+        let icons = self
+            .caches
+            .iter()
+            .filter_map(|cache| cache.icon(icon_name))
+            .collect::<Vec<_>>();
+        let mut seen = BTreeSet::<&Path>::new();
+
+        icons
+            .into_iter()
+            .flat_map(|icon| icon.images())
+            .filter(move |image| seen.insert(image.directory))
+    }
+
+    /// Returns an iterator over the names of all icons in all caches.
+    ///
+    /// Names present in several caches are only returned once, the first time they're found.
+    pub fn names(&self) -> impl Iterator<Item = &'a CStr> {
+        let mut seen = BTreeSet::new();
+
+        self.caches
+            .iter()
+            .flat_map(|cache| cache.names())
+            .filter(move |name| seen.insert(*name))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::raw::Flags;
+    use crate::write::CacheBuilder;
+    use std::collections::HashSet;
+
+    static SAMPLE_INDEX_FILE: &[u8] = include_bytes!("../assets/icon-theme.cache");
+
+    #[test]
+    fn all_images_across_chain() {
+        let png = Flags::new(Flags::HAS_SUFFIX_PNG);
+
+        let mut builder = CacheBuilder::new();
+        builder
+            .add_image("app", "16x16/apps", png)
+            .add_image("app", "32x32/apps", png);
+        let child = builder.build();
+
+        let mut builder = CacheBuilder::new();
+        builder
+            .add_image("app", "32x32/apps", Flags::new(Flags::HAS_SUFFIX_XPM))
+            .add_image("app", "256x256/apps", png);
+        let parent = builder.build();
+
+        let chain = IconCacheChain::new(vec![
+            IconCache::new_from_bytes(&child).unwrap(),
+            IconCache::new_from_bytes(&parent).unwrap(),
+        ]);
+
+        let images = chain
+            .all_images_for("app")
+            .map(|image| (image.directory.to_str().unwrap(), image.icon_flags))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            images,
+            [
+                ("16x16/apps", png),
+                ("32x32/apps", png),
+                ("256x256/apps", png)
+            ]
+        );
+
+        assert_eq!(chain.all_images_for("nonexistent-icon").count(), 0);
+    }
+
+    #[test]
+    fn fallback_to_parent() {
+        let mut builder = CacheBuilder::new();
+        builder
+            .add_image("mpv", "scalable/apps", Flags::new(Flags::HAS_SUFFIX_SVG))
+            .add_image(
+                "only-in-child",
+                "16x16/apps",
+                Flags::new(Flags::HAS_SUFFIX_PNG),
+            );
+        let child = builder.build();
+
+        let chain = IconCacheChain::new(vec![
+            IconCache::new_from_bytes(&child).unwrap(),
+            IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap(),
+        ]);
+
+        // `mpv` exists in both caches; the child's comes first
+        assert_eq!(chain.icon("mpv").unwrap().image_list().unwrap().len(), 1);
+        assert!(chain.icon("only-in-child").is_some());
+
+        // `nvim` only exists in the parent
+        assert!(chain.icon("nvim").is_some());
+        assert!(chain.contains("nvim"));
+
+        assert!(chain.icon("nonexistent-icon").is_none());
+        assert!(!chain.contains("nonexistent-icon"));
+
+        let names = chain.names().collect::<Vec<_>>();
+        assert_eq!(names.len(), 563 + 1);
+        assert_eq!(names.iter().collect::<HashSet<_>>().len(), names.len());
+        assert!(names.contains(&c"only-in-child"));
+    }
+}
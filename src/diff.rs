@@ -0,0 +1,100 @@
+//! Compare the icons of two caches, e.g. before and after regenerating a theme's cache.
+
+use crate::{Icon, IconCache};
+use alloc::collections::BTreeSet;
+use core::ffi::CStr;
+use std::path::Path;
+
+/// The differences between two caches, see [IconCache::diff].
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct CacheDiff<'a> {
+    /// Names of the icons only in the other cache
+    pub added: BTreeSet<&'a CStr>,
+    /// Names of the icons only in this cache
+    pub removed: BTreeSet<&'a CStr>,
+    /// Names of the icons in both caches whose images differ
+    pub changed: BTreeSet<&'a CStr>,
+}
+
+impl CacheDiff<'_> {
+    /// Returns `true` if both caches contain the same icons with the same images.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl<'a> IconCache<'a> {
+    /// Compare the icons in this cache against those in `other`.
+    ///
+    /// An icon in both caches is considered changed if it has a different amount of images, or
+    /// its images are in a different set of directories. The formats and image data of the
+    /// images are not compared.
+    pub fn diff(&self, other: &IconCache<'a>) -> CacheDiff<'a> {
+        let mut diff = CacheDiff::default();
+
+        for icon in self.iter() {
+            match other.icon(icon.name.to_bytes()) {
+                None => {
+                    diff.removed.insert(icon.name);
+                }
+                Some(other_icon) if !same_images(&icon, &other_icon) => {
+                    diff.changed.insert(icon.name);
+                }
+                Some(_) => {}
+            }
+        }
+
+        diff.added = other
+            .names()
+            .filter(|name| !self.contains(name.to_bytes()))
+            .collect();
+
+        diff
+    }
+}
+
+fn same_images(icon: &Icon, other: &Icon) -> bool {
+    image_count(icon) == image_count(other) && directories(icon) == directories(other)
+}
+
+fn image_count(icon: &Icon) -> Option<u32> {
+    icon.image_list().map(|image_list| image_list.len())
+}
+
+fn directories<'a>(icon: &Icon<'a>) -> BTreeSet<&'a Path> {
+    icon.images().map(|image| image.directory).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{SAMPLE_INDEX_FILE, offset_of};
+
+    #[test]
+    fn diff_tweaked_copy() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+        assert!(cache.diff(&cache).is_empty());
+
+        let mut bytes = SAMPLE_INDEX_FILE.to_vec();
+
+        // Drop the last image of `mpv`
+        let image_list = offset_of(cache.icon("mpv").unwrap().image_list().unwrap().raw_list);
+        bytes[image_list..][..4].copy_from_slice(&4u32.to_be_bytes());
+
+        // Rename `nvim` to `mvim`, which also moves it out of the bucket it is looked up in
+        let name = offset_of(cache.icon("nvim").unwrap().name);
+        bytes[name] = b'm';
+
+        let other = IconCache::new_from_bytes(&bytes).unwrap();
+        let diff = cache.diff(&other);
+        assert_eq!(diff.added, BTreeSet::from([c"mvim"]));
+        assert_eq!(diff.removed, BTreeSet::from([c"nvim"]));
+        assert_eq!(diff.changed, BTreeSet::from([c"mpv"]));
+
+        // The other way around
+        let diff = other.diff(&cache);
+        assert_eq!(diff.added, BTreeSet::from([c"nvim"]));
+        assert_eq!(diff.removed, BTreeSet::from([c"mvim"]));
+        assert_eq!(diff.changed, BTreeSet::from([c"mpv"]));
+    }
+}
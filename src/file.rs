@@ -1,12 +1,13 @@
 //! Load icon caches from a file path in a safe manner
 
-use crate::IconCache;
+use crate::{IconCache, IconCacheError};
 use file_lock::FileLock;
-use memmap2::Mmap;
-use std::error::Error;
+use memmap2::{Advice, Mmap, MmapMut, MmapOptions};
 use std::ops::Deref;
-use std::os::fd::AsRawFd;
-use std::path::Path;
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 /// Reexports `file_lock` and `memmap2`, which are used in the [OwnedIconCache] type.
 pub mod reexports {
@@ -17,45 +18,310 @@ pub mod reexports {
 /// Provides access to an [IconCache] constructed from a file that is guaranteed not to be modified.
 ///
 /// `OwnedIconCache` holds a lock on the cache file and creates a memory-mapped region with the file's
-/// contents inside. It does not copy the file contents.
+/// contents inside. It does not copy the file contents, with one exception: little-endian caches
+/// are converted to the big-endian byte order [IconCache] reads (see
+/// [IconCache::swap_byte_order]) in a private, copy-on-write mapping, which leaves the file
+/// untouched but costs a copy of every page.
+///
+/// Caches opened with [open_unlocked](Self::open_unlocked) or mapped from an open file with
+/// [from_file](Self::from_file) don't hold a lock, and rely on the caller to guarantee the file
+/// isn't modified instead.
 ///
 /// To access the icon cache, use [OwnedIconCache::icon_cache]
 #[derive(Debug)]
 pub struct OwnedIconCache {
-    pub lock: FileLock,
+    /// The lock held on the cache file, or `None` if it was opened with
+    /// [open_unlocked](Self::open_unlocked) or [from_file](Self::from_file)
+    pub lock: Option<FileLock>,
     pub memmap: Mmap,
+    /// The path the cache was opened from, if any
+    pub(crate) path: Option<PathBuf>,
+}
+
+/// The kind of lock an [OwnedIconCache] takes on its file.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum LockMode {
+    /// A read lock: any amount of processes can hold a shared lock on the same file at once, but
+    /// not while another holds an exclusive lock.
+    #[default]
+    Shared,
+    /// A write lock, held by one process at a time while no other process holds any lock on the
+    /// file.
+    ///
+    /// Requires write permission on the file, although the file is never written to: opening a
+    /// system cache such as `/usr/share/icons/hicolor/icon-theme.cache` this way fails with
+    /// [PermissionDenied](std::io::ErrorKind::PermissionDenied) for anyone but root.
+    Exclusive,
 }
 
 impl OwnedIconCache {
-    /// Open and lock a file. This call may block waiting to acquire a lock if an exclusive lock
-    /// is already held.
+    /// Open and lock a file with a shared lock. This call may block waiting to acquire a lock if
+    /// an exclusive lock is already held.
     ///
     /// If this behaviour is undesirable, use [open_non_blocking](Self::open_non_blocking) instead.
     pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
-        Self::create(path, true)
+        Self::open_with(path, LockMode::Shared, true)
     }
 
-    /// Open and lock a file, returning an error if an exclusive lock on the file was already held
-    /// by another process.
+    /// Open and lock a file with a shared lock, returning an error if an exclusive lock on the
+    /// file was already held by another process.
     pub fn open_non_blocking(path: impl AsRef<Path>) -> std::io::Result<Self> {
-        Self::create(path, false)
+        Self::open_with(path, LockMode::Shared, false)
+    }
+
+    /// Open and lock a file with a shared lock, waiting at most `timeout` for an exclusive lock
+    /// held by another process to be released.
+    ///
+    /// This retries [open_non_blocking](Self::open_non_blocking) with a small, growing backoff
+    /// until it succeeds or `timeout` has passed, in which case an error of kind
+    /// [WouldBlock](std::io::ErrorKind::WouldBlock) is returned. Other errors, e.g. the file not
+    /// existing, are returned right away.
+    pub fn open_timeout(path: impl AsRef<Path>, timeout: Duration) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let deadline = Instant::now() + timeout;
+        let mut backoff = Duration::from_millis(1);
+
+        loop {
+            match Self::open_non_blocking(path) {
+                Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {}
+                result => return result,
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::WouldBlock,
+                    "timed out waiting for a lock on the cache file",
+                ));
+            }
+
+            std::thread::sleep(backoff.min(deadline - now));
+            backoff = (backoff * 2).min(Duration::from_millis(50));
+        }
+    }
+
+    /// Open and lock a file with a shared lock, so that other readers can open the same file
+    /// concurrently. This is what [open](Self::open) does; this function spells it out.
+    pub fn open_shared(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::open_with(path, LockMode::Shared, true)
+    }
+
+    /// Open and lock a file with a lock of the given `mode`.
+    ///
+    /// If `blocking`, this call waits until the lock can be acquired. Otherwise, it returns an
+    /// error if a conflicting lock is held by another process: any lock for
+    /// [LockMode::Exclusive], or an exclusive lock for [LockMode::Shared].
+    ///
+    /// [LockMode::Exclusive] requires write permission on the file.
+    pub fn open_with(
+        path: impl AsRef<Path>,
+        mode: LockMode,
+        blocking: bool,
+    ) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        // `file_lock` takes an exclusive lock on files opened for writing, and a shared lock
+        // otherwise. An exclusive lock is the only reason to open the file for writing: the cache
+        // is never written to.
+        let options = file_lock::FileOptions::new()
+            .read(true)
+            .write(mode == LockMode::Exclusive);
+        let lock = FileLock::lock(path, blocking, options)?;
+
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            ..Self::from_lock(lock)?
+        })
+    }
+
+    /// Open and memory-map a file without locking it.
+    ///
+    /// Advisory locks can block or fail on read-only file systems and network mounts; this skips
+    /// them entirely.
+    ///
+    /// # Safety
+    ///
+    /// Without a lock, nothing prevents another process from modifying the file while it's mapped,
+    /// which is undefined behaviour. The caller must guarantee the file is not modified for as long
+    /// as the returned `OwnedIconCache` lives. Replacing the file (e.g. by renaming a new file over
+    /// it, as `gtk-update-icon-cache` does) is fine: that doesn't modify the mapped file.
+    pub unsafe fn open_unlocked(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)?;
+        // SAFETY: upheld by the caller
+        let memmap = unsafe { map(file.as_raw_fd())? };
+
+        Ok(Self {
+            lock: None,
+            memmap,
+            path: Some(path.to_path_buf()),
+        })
+    }
+
+    /// Memory-map a file the caller already opened, without re-opening or locking it, e.g. because
+    /// the caller holds a lock on it already.
+    ///
+    /// The file only needs to be open for reading. It may be closed once this returns: the mapping
+    /// stays valid.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee the file is not modified for as long as the returned
+    /// `OwnedIconCache` lives; see [open_unlocked](Self::open_unlocked).
+    pub unsafe fn from_file(file: &std::fs::File) -> std::io::Result<Self> {
+        // SAFETY: upheld by the caller
+        unsafe { Self::from_raw_fd(file.as_raw_fd()) }
+    }
+
+    /// Memory-map the file behind an open file descriptor, without re-opening or locking it.
+    ///
+    /// See [from_file](Self::from_file).
+    ///
+    /// # Safety
+    ///
+    /// `fd` must be an open file descriptor, and the caller must guarantee the file is not modified
+    /// for as long as the returned `OwnedIconCache` lives.
+    pub unsafe fn from_raw_fd(fd: RawFd) -> std::io::Result<Self> {
+        // SAFETY: upheld by the caller
+        let memmap = unsafe { map(fd)? };
+
+        Ok(Self {
+            lock: None,
+            memmap,
+            path: None,
+        })
+    }
+
+    /// Open and lock a file with a shared lock, and map it copy-on-write: the mapped bytes can be
+    /// modified, e.g. to fix up a corrupt cache or [swap its byte order](IconCache::swap_byte_order),
+    /// without touching the file.
+    ///
+    /// Writes to the mapping are private to it, and never persist to the file on disk. Pages are
+    /// only copied once written to. This call may block; see [open](Self::open).
+    pub fn open_cow(path: impl AsRef<Path>) -> std::io::Result<CowIconCache> {
+        let options = file_lock::FileOptions::new().read(true).write(false);
+        let lock = FileLock::lock(path, true, options)?;
+        // SAFETY: we hold `lock`, which claims that the file will not change throughout the
+        // lifetime of the lock. Writes to a private mapping don't change the file either.
+        let memmap = unsafe { MmapOptions::new().map_copy(&lock.file)? };
+
+        Ok(CowIconCache { lock, memmap })
+    }
+
+    /// Open the cache of the icon theme named `theme_name`, e.g. `hicolor`, from the first of the
+    /// [icon base directories](icon_base_dirs) holding a `<theme_name>/icon-theme.cache`.
+    ///
+    /// Returns a [NotFound](std::io::ErrorKind::NotFound) error listing the searched paths if no
+    /// base directory holds a cache for the theme. Opening the cache may block; see
+    /// [open](Self::open).
+    pub fn open_theme(theme_name: &str) -> std::io::Result<Self> {
+        let candidates = icon_base_dirs()
+            .into_iter()
+            .map(|dir| dir.join(theme_name).join("icon-theme.cache"))
+            .collect::<Vec<_>>();
+
+        match candidates.iter().find(|path| path.is_file()) {
+            Some(path) => Self::open(path),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no cache for icon theme {theme_name:?} in any of {candidates:?}"),
+            )),
+        }
+    }
+
+    /// Open the `icon-theme.cache` of every icon theme in `root`, e.g. `/usr/share/icons`.
+    ///
+    /// Returns the caches paired with the name of their theme (the name of the directory they're
+    /// in), sorted by name. Themes without a cache, or whose cache could not be opened or parsed,
+    /// are skipped. Caches are opened with [open_non_blocking](Self::open_non_blocking), so a
+    /// cache that another process holds an exclusive lock on is skipped as well.
+    ///
+    /// Returns an error only if `root` itself could not be read.
+    pub fn open_all(root: impl AsRef<Path>) -> std::io::Result<Vec<(String, OwnedIconCache)>> {
+        let mut caches = vec![];
+
+        for entry in std::fs::read_dir(root)? {
+            let Ok(entry) = entry else {
+                continue;
+            };
+
+            let Ok(cache) = Self::open_non_blocking(entry.path().join("icon-theme.cache")) else {
+                continue;
+            };
+
+            if cache.icon_cache().is_err() {
+                continue;
+            }
+
+            let theme = entry.file_name().to_string_lossy().into_owned();
+            caches.push((theme, cache));
+        }
+
+        caches.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Ok(caches)
     }
 
     /// Access the icon cache held by this `OwnedIconCache`.
     ///
     /// Returns an error if the cache could not be parsed.
-    pub fn icon_cache<'a>(&'a self) -> Result<IconCache<'a>, Box<dyn Error + 'a>> {
+    pub fn icon_cache<'a>(&'a self) -> Result<IconCache<'a>, IconCacheError> {
         let bytes = self.memmap.deref();
         
         IconCache::new_from_bytes(bytes)
     }
 
-    fn create(path: impl AsRef<Path>, blocking: bool) -> std::io::Result<Self> {
-        let path = path.as_ref();
-        let options = file_lock::FileOptions::new().read(true).write(false); // we explicitly do NOT want to write to the cache!
-        let lock = FileLock::lock(path, blocking, options)?;
-        
-        Self::from_lock(lock)
+    /// Returns the length of the mapped cache file in bytes.
+    pub fn len(&self) -> usize {
+        self.memmap.len()
+    }
+
+    /// Returns `true` if the mapped cache file is empty, in which case it can't be parsed.
+    pub fn is_empty(&self) -> bool {
+        self.memmap.is_empty()
+    }
+
+    /// Returns the path the cache was opened from, or `None` if it was created from a lock or an
+    /// open file (see [from_lock](Self::from_lock) and [from_file](Self::from_file)).
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Advise the kernel to read the whole cache into memory ahead of time.
+    ///
+    /// Pages of the mapped file are otherwise only read when first accessed. Since lookups jump
+    /// around the file by hash, the first lookups in a cold cache may each page-fault. For
+    /// workloads iterating over the whole cache instead, consider advising
+    /// [memmap2::Advice::Sequential] on [memmap](Self::memmap) directly.
+    ///
+    /// This only gives a hint: reading ahead happens in the background, and may be ignored.
+    pub fn prefetch(&self) -> std::io::Result<()> {
+        self.memmap.advise(Advice::WillNeed)
+    }
+
+    /// Returns `true` if `theme_dir` was modified after the cache file was, meaning the cache may
+    /// not reflect the theme's current contents.
+    ///
+    /// This is the same check GTK performs before trusting a cache: like GTK, modification times
+    /// are compared in whole seconds.
+    ///
+    /// For caches opened with [open_unlocked](Self::open_unlocked), the modification time of the
+    /// file currently at the cache's path is used, which may not be the mapped file if it has since
+    /// been replaced.
+    pub fn is_stale(&self, theme_dir: &Path) -> std::io::Result<bool> {
+        let cache_metadata = match (&self.lock, &self.path) {
+            (Some(lock), _) => lock.file.metadata()?,
+            (None, Some(path)) => std::fs::metadata(path)?,
+            (None, None) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "cache has neither a locked file nor a path",
+                ));
+            }
+        };
+        let cache_mtime = cache_metadata.mtime();
+        let theme_mtime = std::fs::metadata(theme_dir)?.mtime();
+
+        Ok(theme_mtime > cache_mtime)
     }
 
     /// Create a `OwnedIconCache` from a locked file
@@ -63,20 +329,106 @@ impl OwnedIconCache {
         let fd = lock.file.as_raw_fd();
         // SAFETY: we hold `lock`, which claims that `fd` will not change (unless done by us, which we won't)
         // throughout the lifetime of the lock
-        let memmap = unsafe { Mmap::map(fd)? };
+        let memmap = unsafe { map(fd)? };
+
+        Ok(Self {
+            lock: Some(lock),
+            memmap,
+            path: None,
+        })
+    }
+}
 
-        Ok(Self { lock, memmap })
+/// Memory-map the file behind `fd`. Little-endian caches are mapped privately and converted to
+/// big-endian; if that fails, the cache is mapped as-is so that parsing it reports the error.
+///
+/// # Safety
+///
+/// `fd` must be an open file descriptor of a file that is not modified while mapped.
+unsafe fn map(fd: RawFd) -> std::io::Result<Mmap> {
+    // SAFETY: upheld by the caller
+    let memmap = unsafe { Mmap::map(fd)? };
+    if !IconCache::is_byte_swapped(&memmap) {
+        return Ok(memmap);
+    }
+
+    // SAFETY: upheld by the caller. Writes to a private mapping don't change the file.
+    let mut swapped = unsafe { MmapOptions::new().map_copy(fd)? };
+    if IconCache::swap_byte_order(&mut swapped).is_err() {
+        return Ok(memmap);
+    }
+
+    swapped.make_read_only()
+}
+
+/// Returns the directories icon themes are looked up in, in order of precedence, as laid out by
+/// the freedesktop icon theme specification:
+///
+/// 1. `$HOME/.icons`
+/// 2. `$XDG_DATA_HOME/icons`, or `$HOME/.local/share/icons`
+/// 3. `icons` in each of `$XDG_DATA_DIRS`, or in `/usr/local/share` and `/usr/share`
+/// 4. `/usr/share/pixmaps`
+///
+/// Directories relying on `$HOME` are left out if it isn't set. The directories aren't checked
+/// for existence.
+pub fn icon_base_dirs() -> Vec<PathBuf> {
+    let env_path = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+    let home = env_path("HOME").map(PathBuf::from);
+
+    let mut dirs = vec![];
+
+    if let Some(home) = &home {
+        dirs.push(home.join(".icons"));
+    }
+
+    match (env_path("XDG_DATA_HOME"), &home) {
+        (Some(data_home), _) => dirs.push(PathBuf::from(data_home).join("icons")),
+        (None, Some(home)) => dirs.push(home.join(".local/share/icons")),
+        (None, None) => {}
+    }
+
+    let data_dirs =
+        env_path("XDG_DATA_DIRS").unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    dirs.extend(std::env::split_paths(&data_dirs).map(|dir| dir.join("icons")));
+
+    dirs.push(PathBuf::from("/usr/share/pixmaps"));
+
+    dirs
+}
+
+/// Provides access to an [IconCache] constructed from a file mapped copy-on-write, see
+/// [OwnedIconCache::open_cow].
+///
+/// The mapped bytes in [memmap](Self::memmap) can be modified in-place; the file stays untouched.
+#[derive(Debug)]
+pub struct CowIconCache {
+    /// The lock held on the cache file
+    pub lock: FileLock,
+    pub memmap: MmapMut,
+}
+
+impl CowIconCache {
+    /// Access the icon cache held by this `CowIconCache`, including any changes made to the
+    /// mapped bytes.
+    ///
+    /// Returns an error if the cache could not be parsed.
+    pub fn icon_cache(&self) -> Result<IconCache<'_>, IconCacheError> {
+        IconCache::new_from_bytes(&self.memmap)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::file::OwnedIconCache;
-    use crate::raw;
+    use crate::file::{LockMode, OwnedIconCache};
     use crate::raw::Offset;
+    use crate::{IconCache, raw};
     use std::error::Error;
+    use std::io::{BufRead, BufReader, Lines, Read, Write};
     use std::ops::Deref;
+    use std::path::Path;
+    use std::process::{Child, ChildStdout, Command, Stdio};
     use std::sync::LazyLock;
+    use std::time::{Duration, Instant, SystemTime};
     use zerocopy::U16;
 
     use mktemp::Temp;
@@ -133,4 +485,269 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn open_all_themes() -> std::io::Result<()> {
+        let root = Temp::new_dir()?;
+
+        for theme in ["hicolor", "broken", "no-cache", "Adwaita", "locked"] {
+            std::fs::create_dir(root.join(theme))?;
+        }
+        std::fs::write(root.join("hicolor/icon-theme.cache"), SAMPLE_INDEX_FILE)?;
+        std::fs::write(root.join("Adwaita/icon-theme.cache"), SAMPLE_INDEX_FILE)?;
+        std::fs::write(root.join("broken/icon-theme.cache"), b"not a cache")?;
+        std::fs::write(root.join("locked/icon-theme.cache"), SAMPLE_INDEX_FILE)?;
+
+        let holder = LockHolder::spawn(&root.join("locked/icon-theme.cache"), LockMode::Exclusive)?;
+        let caches = OwnedIconCache::open_all(root.as_path())?;
+        holder.release()?;
+
+        let themes = caches
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(themes, ["Adwaita", "hicolor"]);
+        assert!(caches[1].1.icon_cache().unwrap().icon("mpv").is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn stale_when_theme_dir_is_newer() -> std::io::Result<()> {
+        let theme_dir = Temp::new_dir()?;
+        let path = theme_dir.join("icon-theme.cache");
+        std::fs::write(&path, SAMPLE_INDEX_FILE)?;
+
+        let now = SystemTime::now();
+        let set_mtime = |path: &Path, mtime| std::fs::File::open(path)?.set_modified(mtime);
+
+        set_mtime(&path, now)?;
+        set_mtime(&theme_dir, now - Duration::from_secs(10))?;
+
+        let cache = OwnedIconCache::open(&path)?;
+        assert!(!cache.is_stale(&theme_dir)?);
+
+        // e.g. an icon was added after the cache was generated
+        set_mtime(&theme_dir, now + Duration::from_secs(10))?;
+        assert!(cache.is_stale(&theme_dir)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_shared_twice() -> Result<(), Box<dyn Error>> {
+        let temp = create_test_cache()?;
+        let holder = LockHolder::spawn(temp.as_path(), LockMode::Shared)?;
+
+        let second = OwnedIconCache::open_non_blocking(temp.as_path())?;
+        assert!(second.icon_cache()?.icon("mpv").is_some());
+
+        let error =
+            OwnedIconCache::open_with(temp.as_path(), LockMode::Exclusive, false).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::WouldBlock);
+
+        holder.release()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_exclusive() -> Result<(), Box<dyn Error>> {
+        let temp = create_test_cache()?;
+        let file = OwnedIconCache::open_with(temp.as_path(), LockMode::Exclusive, false)?;
+
+        assert_eq!(file.memmap.deref(), SAMPLE_INDEX_FILE);
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_little_endian() -> Result<(), Box<dyn Error>> {
+        let mut bytes = SAMPLE_INDEX_FILE.to_vec();
+        IconCache::swap_byte_order(&mut bytes)?;
+        let temp = Temp::new_file()?;
+        std::fs::write(temp.as_path(), &bytes)?;
+
+        let file = OwnedIconCache::open(temp.as_path())?;
+        assert_eq!(file.memmap.deref(), SAMPLE_INDEX_FILE);
+        assert_eq!(file.icon_cache()?.version(), (1, 0));
+        assert!(file.icon_cache()?.icon("mpv").is_some());
+
+        let handle = std::fs::File::open(temp.as_path())?;
+        // SAFETY: nothing modifies the test cache
+        let file = unsafe { OwnedIconCache::from_file(&handle)? };
+        assert!(file.icon_cache()?.icon("mpv").is_some());
+
+        // The file itself is left as-is
+        assert_eq!(std::fs::read(temp.as_path())?, bytes);
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_cow() -> Result<(), Box<dyn Error>> {
+        let temp = create_test_cache()?;
+        let mut file = OwnedIconCache::open_cow(temp.as_path())?;
+        assert_eq!(file.icon_cache()?.version(), (1, 0));
+
+        // Bump the minor version in memory only
+        file.memmap[3] = 7;
+        assert_eq!(file.icon_cache()?.version(), (1, 7));
+        file.memmap.flush()?;
+
+        drop(file);
+        assert_eq!(std::fs::read(temp.as_path())?, SAMPLE_INDEX_FILE);
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_nonexistent_theme() {
+        let error = OwnedIconCache::open_theme("no-such-icon-theme-exists").unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        assert!(error.to_string().contains("no-such-icon-theme-exists"));
+        assert!(error.to_string().contains("/usr/share/pixmaps"));
+    }
+
+    #[test]
+    fn prefetch() -> Result<(), Box<dyn Error>> {
+        let path = TEMP_FILE.as_path();
+        let file = OwnedIconCache::open_non_blocking(path)?;
+
+        file.prefetch()?;
+        assert!(file.icon_cache()?.icon("mpv").is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_unlocked() -> Result<(), Box<dyn Error>> {
+        let path = TEMP_FILE.as_path();
+        // SAFETY: nothing modifies the test cache
+        let file = unsafe { OwnedIconCache::open_unlocked(path)? };
+
+        assert!(file.lock.is_none());
+        assert_eq!(file.icon_cache()?.version(), (1, 0));
+        assert!(file.icon_cache()?.icon("mpv").is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_open_file() -> Result<(), Box<dyn Error>> {
+        let handle = std::fs::File::open(TEMP_FILE.as_path())?;
+        // SAFETY: nothing modifies the test cache
+        let file = unsafe { OwnedIconCache::from_file(&handle)? };
+        drop(handle);
+
+        assert!(file.lock.is_none());
+        assert!(file.path.is_none());
+        assert!(file.icon_cache()?.icon("mpv").is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn len_and_path() -> Result<(), Box<dyn Error>> {
+        let path = TEMP_FILE.as_path();
+        let file = OwnedIconCache::open_non_blocking(path)?;
+
+        assert_eq!(file.len(), SAMPLE_INDEX_FILE.len());
+        assert!(!file.is_empty());
+        assert_eq!(file.path(), Some(path));
+
+        let handle = std::fs::File::open(path)?;
+        // SAFETY: nothing modifies the test cache
+        let file = unsafe { OwnedIconCache::from_file(&handle)? };
+        assert_eq!(file.len(), SAMPLE_INDEX_FILE.len());
+        assert_eq!(file.path(), None);
+
+        Ok(())
+    }
+
+    /// Holds a lock on the file at `$ICON_CACHE_LOCK_PATH` until stdin is closed: an exclusive
+    /// lock if `$ICON_CACHE_LOCK_EXCLUSIVE` is set, a shared one otherwise.
+    ///
+    /// Locks held by the same process never conflict, so [LockHolder] runs this in a child
+    /// process. Does nothing when run on its own.
+    #[test]
+    #[ignore]
+    fn hold_lock() -> Result<(), Box<dyn Error>> {
+        let Some(path) = std::env::var_os("ICON_CACHE_LOCK_PATH") else {
+            return Ok(());
+        };
+        let mode = match std::env::var_os("ICON_CACHE_LOCK_EXCLUSIVE") {
+            Some(_) => LockMode::Exclusive,
+            None => LockMode::Shared,
+        };
+        let _file = OwnedIconCache::open_with(path, mode, true)?;
+
+        println!("locked");
+        std::io::stdout().flush()?;
+        std::io::stdin().read_to_end(&mut vec![])?;
+
+        Ok(())
+    }
+
+    /// A child process holding a lock on a file, see [hold_lock].
+    struct LockHolder {
+        child: Child,
+        /// Kept open until the child exits, which would fail writing to a closed pipe
+        _stdout: Lines<BufReader<ChildStdout>>,
+    }
+
+    impl LockHolder {
+        /// Spawn the child process, returning once it holds the lock.
+        fn spawn(path: &Path, mode: LockMode) -> std::io::Result<Self> {
+            let mut command = Command::new(std::env::current_exe()?);
+            command
+                .args(["--exact", "file::tests::hold_lock"])
+                .args(["--ignored", "--nocapture"])
+                .env("ICON_CACHE_LOCK_PATH", path)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped());
+            if mode == LockMode::Exclusive {
+                command.env("ICON_CACHE_LOCK_EXCLUSIVE", "1");
+            }
+
+            let mut child = command.spawn()?;
+            let stdout = BufReader::new(child.stdout.take().unwrap());
+            // libtest prints the name of the test on the same line
+            let mut lines = stdout.lines();
+            assert!(lines.any(|line| line.is_ok_and(|line| line.ends_with("locked"))));
+
+            Ok(Self {
+                child,
+                _stdout: lines,
+            })
+        }
+
+        /// Release the lock by closing the child's stdin, and wait for it to exit.
+        fn release(mut self) -> std::io::Result<()> {
+            drop(self.child.stdin.take());
+            assert!(self.child.wait()?.success());
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn open_timeout() -> Result<(), Box<dyn Error>> {
+        let temp = create_test_cache()?;
+        let holder = LockHolder::spawn(temp.as_path(), LockMode::Exclusive)?;
+
+        let timeout = Duration::from_millis(100);
+        let start = Instant::now();
+        let error = OwnedIconCache::open_timeout(temp.as_path(), timeout).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::WouldBlock);
+        assert!(start.elapsed() >= timeout);
+
+        holder.release()?;
+
+        let file = OwnedIconCache::open_timeout(temp.as_path(), Duration::from_secs(5))?;
+        assert!(file.icon_cache()?.icon("mpv").is_some());
+
+        Ok(())
+    }
 }
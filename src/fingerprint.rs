@@ -0,0 +1,125 @@
+//! Content-based fingerprints of icon caches, independent of their layout.
+
+use crate::IconCache;
+use alloc::vec::Vec;
+use core::hash::Hasher;
+use std::os::unix::ffi::OsStrExt;
+
+impl IconCache<'_> {
+    /// Returns a hash of the icons in this cache and the directory and flags of each of their
+    /// images, e.g. to notice that a cache changed without relying on its modification time.
+    ///
+    /// Only content is hashed, not layout: caches holding the same icons and images have the same
+    /// fingerprint, regardless of the order of their hash table, chains, image lists or directory
+    /// list. Image data is not hashed. The fingerprint is stable across processes and versions of
+    /// this crate.
+    pub fn fingerprint(&self) -> u64 {
+        let mut icons = self
+            .iter()
+            .map(|icon| {
+                let mut images = icon
+                    .images()
+                    .map(|image| (image.directory, image.icon_flags.bits().get()))
+                    .collect::<Vec<_>>();
+                images.sort_unstable();
+
+                (icon.name, images)
+            })
+            .collect::<Vec<_>>();
+        icons.sort_unstable();
+
+        let mut hasher = Fnv1a::default();
+        for (name, images) in icons {
+            hasher.write(name.to_bytes_with_nul());
+            hasher.write_u32(images.len() as u32);
+
+            for (directory, flags) in images {
+                hasher.write(directory.as_os_str().as_bytes());
+                hasher.write_u8(0);
+                hasher.write_u16(flags);
+            }
+        }
+
+        hasher.finish()
+    }
+}
+
+/// The 64-bit FNV-1a hash, which unlike std's default hasher is the same in every process.
+///
+/// Integers are hashed in big-endian byte order, so that the hash is the same on every platform.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.write(&value.to_be_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw::Flags;
+    use crate::tests::{SAMPLE_INDEX_FILE, offset_of};
+    use crate::write::CacheBuilder;
+    use zerocopy::IntoBytes;
+
+    #[test]
+    fn fingerprint() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+        let fingerprint = cache.fingerprint();
+
+        // Re-reading the same bytes from another copy
+        let bytes = SAMPLE_INDEX_FILE.to_vec();
+        assert_eq!(
+            IconCache::new_from_bytes(&bytes).unwrap().fingerprint(),
+            fingerprint
+        );
+
+        // The same icons, with the directories and images added in a different order
+        let mut builder = CacheBuilder::new();
+        for directory in cache.directory_list.iter().rev() {
+            builder.add_directory(directory);
+        }
+        for icon in cache.iter() {
+            for image in icon.image_list().unwrap().iter().rev() {
+                builder.add_image(icon.name.to_bytes(), image.directory, image.icon_flags);
+            }
+        }
+        let bytes = builder.build();
+        assert!(bytes != SAMPLE_INDEX_FILE);
+        assert_eq!(
+            IconCache::new_from_bytes(&bytes).unwrap().fingerprint(),
+            fingerprint
+        );
+
+        // Change the flags of the first image of `mpv`
+        let mut bytes = SAMPLE_INDEX_FILE.to_vec();
+        let image_list = offset_of(cache.icon("mpv").unwrap().image_list().unwrap().raw_list);
+        bytes[image_list + 4 + 2..][..2].copy_from_slice(Flags::HAS_SUFFIX_PNG.as_bytes());
+        assert_ne!(
+            IconCache::new_from_bytes(&bytes).unwrap().fingerprint(),
+            fingerprint
+        );
+    }
+}
@@ -10,16 +10,65 @@
 //!
 //! # Feature flags
 //!
-//! * `file`: Enables the [mod@file] module to safely open and memory-map cache files. Enabled by default.
-
-use std::error::Error;
-use std::ffi::CStr;
-use std::path::Path;
-use zerocopy::{network_endian, FromBytes};
-
+//! * `std`: Enables everything that deals with paths, such as the directories of images. Without it,
+//!   this crate is `no_std`, but still needs `alloc`. Enabled by default.
+//! * `file`: Enables the `file` module to safely open and memory-map cache files. Enabled by
+//!   default. Implies `std`.
+//! * `serde`: Implements `Serialize` for [Icon], [Image] and `DirectoryInfo`, e.g. to export the
+//!   contents of a cache to JSON. Implies `std`.
+//! * `rayon`: Enables `IconCache::par_iter` to iterate over the icons in a cache in parallel.
+//!   Implies `std`.
+//! * `image`: Enables `ImageData::to_rgba` to decode the pixel data embedded in a cache into an
+//!   [image](https://docs.rs/image) `RgbaImage`. Implies `std`.
+//! * `watch`: Enables the `watch` module to reload a `file::OwnedIconCache` when its file is
+//!   replaced. Implies `file`.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+use alloc::collections::BTreeMap;
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::ffi::CStr;
+use core::fmt::{Display, Formatter};
+use core::mem::offset_of;
+#[cfg(feature = "std")]
+use core::ops::Range;
+use raw::LengthPrefixed;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::ffi::OsStr;
+#[cfg(feature = "std")]
+use std::os::unix::ffi::OsStrExt;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+use zerocopy::network_endian::U32;
+use zerocopy::{ConvertError, FromBytes, SizeError, network_endian};
+
+mod byte_order;
+pub mod chain;
+#[cfg(feature = "std")]
+pub mod diff;
 #[cfg(feature = "file")]
 pub mod file;
+#[cfg(feature = "std")]
+mod fingerprint;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "image")]
+mod pixdata;
 pub mod raw;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod validate;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "std")]
+pub mod write;
 
 /// Thin wrapper around an in-memory icon cache.
 ///
@@ -27,10 +76,9 @@ pub mod raw;
 ///
 /// `IconCache`'s fields may be interesting for advanced uses, but if all you need is to look up
 /// icons—use [icon](IconCache::icon).
-#[derive(derive_more::Debug, Copy, Clone)]
+#[derive(Copy, Clone)]
 pub struct IconCache<'a> {
     /// The raw bytes representing the cache
-    #[debug(skip)]
     pub bytes: &'a [u8],
     /// Cache header file: contains version and hash & directory list offsets
     pub header: &'a raw::Header,
@@ -40,24 +88,75 @@ pub struct IconCache<'a> {
     pub directory_list: DirectoryList<'a>,
 }
 
+// The zero-copy casts in `new_from_bytes` and on lookup accept any address, since every raw struct
+// is made up of byte-order-aware integers.
+const _: () = assert!(
+    align_of::<raw::Header>() == 1
+        && align_of::<raw::Icon>() == 1
+        && align_of::<raw::Image>() == 1
+        && align_of::<raw::ImageData>() == 1
+        && align_of::<raw::MetaData>() == 1
+);
+
 impl<'a> IconCache<'a> {
-    pub fn new_from_bytes(bytes: &'a [u8]) -> Result<Self, Box<dyn Error + 'a>> {
+    /// Parse an icon cache from its raw bytes, as found in e.g. `icon-theme.cache` files.
+    ///
+    /// Returns an error if the header, hash table or directory list could not be parsed, or if
+    /// the cache's major version is not `1`. Any minor version is accepted. Little-endian caches
+    /// are rejected, see [swap_byte_order](Self::swap_byte_order): `file::OwnedIconCache` and
+    /// [OwnedBytesCache] convert them when loading a cache.
+    ///
+    /// A cache too short to hold its header (e.g. an empty file), or which ends before its hash
+    /// table or directory list does, is [Truncated](IconCacheError::Truncated). Offsets pointing
+    /// past the end of the cache are [invalid](IconCacheError::InvalidOffset).
+    ///
+    /// `bytes` needn't be aligned: unaligned sub-slices of a larger buffer are accepted.
+    pub fn new_from_bytes(bytes: &'a [u8]) -> Result<Self, IconCacheError> {
+        if bytes.len() < size_of::<raw::Header>() {
+            return Err(IconCacheError::Truncated);
+        }
+
         let (header, _) = raw::Header::ref_from_prefix(bytes)?;
-        
+
+        if Self::is_byte_swapped(bytes) {
+            return Err(IconCacheError::ByteSwapped);
+        }
+
+        if header.major_version.get() != 1 {
+            return Err(IconCacheError::UnsupportedVersion {
+                major: header.major_version.get(),
+                minor: header.minor_version.get(),
+            });
+        }
+
         let hash_offset = header.hash.offset.get() as usize;
         let dir_list_offset = header.directory_list.offset.get() as usize;
-        
-        let (hash_len, _) = network_endian::U32::read_from_prefix(&bytes[hash_offset..])?;
-        let (dir_len, _) = network_endian::U32::read_from_prefix(&bytes[dir_list_offset..])?;
-        
-        let (hash, _) = raw::Hash::ref_from_prefix_with_elems(&bytes[hash_offset..], hash_len.get() as usize)?;
-        let (directory_list, _) = raw::DirectoryList::ref_from_prefix_with_elems(&bytes[dir_list_offset..], dir_len.get() as usize)?;
+
+        if hash_offset < size_of::<raw::Header>() || dir_list_offset < size_of::<raw::Header>() {
+            return Err(IconCacheError::BadHeader);
+        }
+
+        if hash_offset > bytes.len() || dir_list_offset > bytes.len() {
+            return Err(IconCacheError::InvalidOffset);
+        }
+
+        // An offset right at the end of the cache leaves no room for the length of the table
+        // it points to: the cache was cut off there
+        let hash_bytes = &bytes[hash_offset..];
+        let dir_list_bytes = &bytes[dir_list_offset..];
+
+        let (hash_len, _) = network_endian::U32::read_from_prefix(hash_bytes)?;
+        let (dir_len, _) = network_endian::U32::read_from_prefix(dir_list_bytes)?;
+
+        let (hash, _) = raw::Hash::ref_from_prefix_with_elems(hash_bytes, hash_len.get() as usize)?;
+        let (directory_list, _) =
+            raw::DirectoryList::ref_from_prefix_with_elems(dir_list_bytes, dir_len.get() as usize)?;
 
         let directory_list = DirectoryList {
             bytes,
             raw_list: directory_list,
         };
-        
+
         Ok(IconCache {
             bytes,
             header,
@@ -66,282 +165,3185 @@ impl<'a> IconCache<'a> {
         })
     }
 
+    /// Parse an icon cache starting at `start` within a larger blob, e.g. an asset file the cache
+    /// was concatenated into. Offsets within the cache are relative to `start`.
+    ///
+    /// See [new_from_bytes](Self::new_from_bytes). A `start` past the end of `bytes` is treated
+    /// as an empty, and thus [truncated](IconCacheError::Truncated), cache.
+    pub fn new_from_bytes_at(bytes: &'a [u8], start: usize) -> Result<Self, IconCacheError> {
+        Self::new_from_bytes(bytes.get(start..).unwrap_or_default())
+    }
+
+    /// Returns the `(major, minor)` version of the cache format.
+    ///
+    /// Only version `1.0` is known, which `gtk-update-icon-cache` has written since its
+    /// introduction. Every `1.x` cache may hold embedded [image data](Image::image_data) and meta
+    /// data, so an image without either simply has none. Caches with a newer minor version are
+    /// read as if they were `1.0`, which they're expected to extend compatibly: data added since
+    /// is ignored.
+    pub fn version(&self) -> (u16, u16) {
+        (
+            self.header.major_version.get(),
+            self.header.minor_version.get(),
+        )
+    }
+
     /// Look up an icon by name in the cache. `icon_name` accepts any type that turns into a byte
     /// slice: typically `str` suffices.
     ///
     /// Returns `None` if no icon by that name exists within the icon theme, or if parsing failed.
     pub fn icon(&self, icon_name: impl AsRef<[u8]>) -> Option<Icon<'a>> {
         let icon_name = icon_name.as_ref();
-        let hash = icon_str_hash(icon_name);
-        let n_buckets = self.hash.n_buckets.get();
-        let bucket = hash % n_buckets;
 
-        let icons = self.icon_chain(bucket)?.iter(self.bytes);
+        self.icon_by_hash(icon_str_hash(icon_name), icon_name)
+    }
 
-        for icon in icons {
-            let Ok(name) = icon.name.str_at(self.bytes) else {
-                continue;
-            };
+    /// Look up an icon by its name in bytes.
+    ///
+    /// Names are compared byte for byte, and never need to be valid UTF-8: icons with names that
+    /// aren't, which are legal in a cache, are found as well. [iter](Self::iter) and
+    /// [names](Self::names) include such icons too. This is the same lookup as [icon](Self::icon),
+    /// without the generic argument.
+    pub fn icon_bytes(&self, icon_name: &[u8]) -> Option<Icon<'a>> {
+        self.icon(icon_name)
+    }
 
-            if name.to_bytes() == icon_name {
-                return Some(Icon {
-                    name,
-                    image_list: ImageList::from_icon(icon, self.bytes)?,
-                });
-            }
-        }
+    /// Look up an icon by name, using a precomputed `hash` of the name rather than hashing it
+    /// again: `hash` must be the [icon_str_hash] of `icon_name`.
+    ///
+    /// The name of every icon in the bucket picked by `hash` is still compared against
+    /// `icon_name`, so a wrong hash can't return the wrong icon: at worst, the icon isn't found.
+    pub fn icon_by_hash(&self, hash: u32, icon_name: impl AsRef<[u8]>) -> Option<Icon<'a>> {
+        let (name, icon) = self.find_raw_icon(hash, icon_name.as_ref())?;
 
-        None
+        Some(self.icon_with_name(name, icon))
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = Icon<'a>> {
-        (0..self.hash.n_buckets.get())
-            .filter_map(|bucket| self.icon_chain(bucket))
-            .flat_map(|chain| chain.iter(self.bytes))
-            .filter_map(|icon| {
-                Some(Icon {
-                    name: icon.name.str_at(self.bytes).ok()?,
-                    image_list: ImageList::from_icon(icon, self.bytes)?,
-                })
-            })
-    }
+    /// Look up an icon by name, ignoring ASCII case: `Firefox` finds an icon named `firefox`.
+    ///
+    /// An exact match is looked up first. If there is none, this falls back to comparing against
+    /// every icon in the cache, which is O(n) in the amount of icons. If multiple icons match,
+    /// the first in bucket order is returned.
+    pub fn icon_ci(&self, icon_name: &str) -> Option<Icon<'a>> {
+        if let Some(icon) = self.icon(icon_name) {
+            return Some(icon);
+        }
 
-    fn icon_chain(&self, bucket: u32) -> Option<&'a raw::Icon> {
-        debug_assert!(bucket < self.hash.n_buckets.get());
+        self.iter().find(|icon| {
+            icon.name
+                .to_bytes()
+                .eq_ignore_ascii_case(icon_name.as_bytes())
+        })
+    }
 
-        let offset = self.hash.icon[bucket as usize];
-        // A bucket may be empty!
-        if offset.is_null() {
-            return None;
-        }
+    /// Look up an icon by name, falling back to each of the names in `fallback` in order if it
+    /// doesn't exist, e.g. `["application-x-executable", "image-missing"]`.
+    ///
+    /// Returns `None` only if neither `icon_name` nor any of the fallbacks exist.
+    pub fn icon_or(&self, icon_name: impl AsRef<[u8]>, fallback: &[&str]) -> Option<Icon<'a>> {
+        self.icon(icon_name)
+            .or_else(|| fallback.iter().find_map(|name| self.icon(name)))
+    }
 
-        offset.at(self.bytes).ok()
+    /// Look up every icon in `names`, e.g. all icons of an application menu at once.
+    ///
+    /// The returned icons are in the same order as `names`, with `None` for icons that don't
+    /// exist.
+    pub fn icons<N: AsRef<[u8]>>(&self, names: &[N]) -> Vec<Option<Icon<'a>>> {
+        names.iter().map(|name| self.icon(name)).collect()
     }
-}
 
-/// List of directories in the icon theme with paths relative to the root of the icon theme.
-#[derive(derive_more::Debug, Copy, Clone)]
-pub struct DirectoryList<'a> {
-    #[debug(skip)]
-    #[allow(unused)] // clippy thinks this is unused but it isn't? maybe because of the Debug
-    bytes: &'a [u8],
-    pub raw_list: &'a raw::DirectoryList,
-}
+    /// Returns `true` if an icon by the name `icon_name` exists within the icon theme.
+    ///
+    /// Same as checking `icon(icon_name).is_some()`: neither resolves the icon's image list.
+    pub fn contains(&self, icon_name: impl AsRef<[u8]>) -> bool {
+        let icon_name = icon_name.as_ref();
 
-impl<'a> DirectoryList<'a> {
-    /// Returns the amount of directories in this list
-    #[inline(always)]
-    pub fn len(&self) -> u32 {
-        self.raw_list.n_directories.get()
+        self.find_raw_icon(icon_str_hash(icon_name), icon_name)
+            .is_some()
     }
 
-    /// Returns `true` if the list is empty
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
+    /// Checks which of `names` are provided by this icon theme.
+    ///
+    /// Returns the amount of names that were found, and the names that are missing, in the order
+    /// they appear in `names`.
+    pub fn coverage_of<'n, N: AsRef<[u8]>>(&self, names: &'n [N]) -> (usize, Vec<&'n [u8]>) {
+        let missing = names
+            .iter()
+            .map(AsRef::as_ref)
+            .filter(|name| !self.contains(name))
+            .collect::<Vec<_>>();
+
+        (names.len() - missing.len(), missing)
     }
 
-    /// Access a directory by its index in the list.
+    /// Look up an icon by name like [icon](Self::icon), also returning the amount of icons in the
+    /// hash chain that were examined to find it, or to conclude it doesn't exist.
     ///
-    /// Returns `None` if the index larger than the length of the list.
-    pub fn dir(&self, idx: u32) -> Option<&'a Path> {
-        if idx >= self.len() {
-            return None;
-        }
+    /// An icon at the start of its chain takes one probe. Long chains, where many names hash to
+    /// the same bucket, make lookups of the icons at their end (and of missing icons) slower.
+    pub fn icon_with_probes(&self, icon_name: impl AsRef<[u8]>) -> (Option<Icon<'a>>, u32) {
+        let (found, probes) = self.probe_raw_icon(icon_str_hash(&icon_name), icon_name.as_ref());
+
+        let icon = found.map(|(name, icon)| self.icon_with_name(name, icon));
 
-        self.raw_list.directory[idx as usize].path_at(self.bytes)
+        (icon, probes)
     }
 
-    /// Returns an iterator over the directory list
-    pub fn iter(&self) -> impl Iterator<Item = &'a Path> {
-        (0..self.len()).filter_map(|idx| self.dir(idx))
+    fn find_raw_icon(&self, hash: u32, icon_name: &[u8]) -> Option<(&'a CStr, &'a raw::Icon)> {
+        let (found, _) = self.probe_raw_icon(hash, icon_name);
+        found
     }
-}
 
-/// An icon, identified by its name, and the list of images provided by the icon theme for this icon.
-#[derive(Debug, Copy, Clone)]
-pub struct Icon<'a> {
-    pub name: &'a CStr,
-    pub image_list: ImageList<'a>,
-}
+    /// Find the icon named `icon_name` in the chain of the bucket picked by `hash`, returning it
+    /// along with the amount of icons in the chain that were examined.
+    fn probe_raw_icon(
+        &self,
+        hash: u32,
+        icon_name: &[u8],
+    ) -> (Option<(&'a CStr, &'a raw::Icon)>, u32) {
+        let n_buckets = self.hash.n_buckets.get();
+        // A malformed cache may not have any buckets at all
+        if n_buckets == 0 {
+            return (None, 0);
+        }
 
-#[derive(derive_more::Debug, Copy, Clone)]
-pub struct ImageList<'a> {
-    #[debug(skip)]
-    bytes: &'a [u8],
-    pub raw_list: &'a raw::ImageList,
-}
+        let bucket = hash % n_buckets;
 
-impl<'a> ImageList<'a> {
-    fn from_icon(icon: &raw::Icon, bytes: &'a [u8]) -> Option<ImageList<'a>> {
-        Some(Self {
-            bytes,
-            raw_list: icon.image_list.at(bytes).ok()?,
-        })
-    }
+        let Some(first) = self.icon_chain(bucket) else {
+            return (None, 0);
+        };
 
-    /// Returns the amount of images in this list
-    pub fn len(&self) -> u32 {
-        self.raw_list.n_images.get()
+        let mut probes = 0;
+        for icon in first.iter(self.bytes) {
+            probes += 1;
+
+            let Ok(name) = icon.name.str_at(self.bytes) else {
+                continue;
+            };
+
+            if name.to_bytes() == icon_name {
+                return (Some((name, icon)), probes);
+            }
+        }
+
+        (None, probes)
     }
 
-    /// Returns `true` if the list is empty
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
+    /// Returns an iterator over all icons in the cache, in bucket order.
+    ///
+    /// Icons whose name cannot be parsed, e.g. because it is not nul-terminated before the end of
+    /// the cache, are skipped. Image lists are only resolved when asked for with
+    /// [Icon::image_list], so iterating over icons costs little more than over their
+    /// [names](Self::names).
+    pub fn iter(&self) -> IconIter<'a> {
+        IconIter {
+            raw_icons: self.raw_icons(),
+        }
     }
 
-    /// Access an image by its index in the list.
+    /// Returns the amount of distinct 64-byte cache lines read while looking up `icon_name` with
+    /// [icon](Self::icon): the header's hash offset, the bucket, and every icon and icon name in
+    /// the bucket's chain up to and including the matching icon.
     ///
-    /// Returns `None` if the index larger than the length of the list, or if the image data
-    /// failed to parse.
-    pub fn image(&self, idx: u32) -> Option<Image<'a>> {
-        if idx >= self.len() {
-            return None;
+    /// The image list of the matching icon is not included.
+    pub fn lookup_footprint(&self, icon_name: impl AsRef<[u8]>) -> usize {
+        const CACHE_LINE: usize = 64;
+
+        let icon_name = icon_name.as_ref();
+        let mut lines = BTreeSet::new();
+        let mut touch = |offset: usize, len: usize| {
+            lines.extend(offset / CACHE_LINE..=(offset + len.max(1) - 1) / CACHE_LINE);
+        };
+
+        let n_buckets = self.hash.n_buckets.get();
+        let hash_offset = self.header.hash.offset.get() as usize;
+        touch(
+            offset_of!(raw::Header, hash),
+            size_of::<raw::Offset<U32, raw::Hash>>(),
+        );
+        touch(hash_offset, size_of::<U32>());
+
+        if n_buckets == 0 {
+            return lines.len();
         }
 
-        let raw_image = &self.raw_list.images[idx as usize];
+        let bucket = icon_str_hash(icon_name) % n_buckets;
+        touch(
+            hash_offset + size_of::<U32>() * (1 + bucket as usize),
+            size_of::<U32>(),
+        );
 
-        // TODO: how does the overhead of re-interpreting the header and directory list here over
-        // passing those down from the cache struct, or alternatively re-introducing the ref to cache?
-        let (header, _) = raw::Header::ref_from_prefix(self.bytes).ok()?;
-        let directory_list = header.directory_list.at(self.bytes).ok()?;
-        let directory = directory_list.directory[raw_image.directory_index.get() as usize]
-            .path_at(self.bytes)?;
+        let mut visited = BTreeSet::new();
+        let mut offset = self.hash.icon[bucket as usize];
+        while !offset.is_null() && visited.insert(offset.offset.get()) {
+            touch(offset.offset.get() as usize, size_of::<raw::Icon>());
 
-        let icon_flags = raw_image.icon_flags;
+            let Ok(icon) = offset.at(self.bytes) else {
+                break;
+            };
 
-        let mut image_data = None;
+            if let Ok(name) = icon.name.str_at(self.bytes) {
+                touch(
+                    icon.name.offset.get() as usize,
+                    name.to_bytes_with_nul().len(),
+                );
 
-        if raw_image.image_data.offset != 0 {
-            let &raw::ImageData {
-                image_pixel_data,
-                image_meta_data,
-                image_pixel_data_length,
-                image_pixel_data_type,
-            } = raw_image.image_data.at(self.bytes).ok()?;
+                if name.to_bytes() == icon_name {
+                    break;
+                }
+            }
 
-            image_data = Some(ImageData {
-                image_pixel_data: *image_pixel_data.at(self.bytes).ok()?,
-                image_meta_data: image_meta_data.at(self.bytes).ok()?,
-                image_pixel_data_type: *image_pixel_data_type.at(self.bytes).ok()?,
-                image_pixel_data_length: *image_pixel_data_length.at(self.bytes).ok()?,
-            });
+            offset = icon.chain;
         }
 
-        Some(Image {
-            directory,
-            icon_flags,
-            image_data,
-        })
+        lines.len()
     }
 
-    /// Returns an iterator over the image list
-    pub fn iter(&self) -> impl Iterator<Item = Image<'a>> {
-        (0..self.len()).filter_map(|idx| self.image(idx))
+    /// Returns an iterator over every image in the cache, paired with the name of the icon it
+    /// belongs to.
+    ///
+    /// Images are resolved against the directory list each image list already holds, so the
+    /// directory list isn't parsed again per image. Images that fail to parse are skipped.
+    pub fn all_images(&self) -> impl Iterator<Item = (&'a CStr, Image<'a>)> {
+        self.iter()
+            .filter_map(|icon| Some((icon.name, icon.image_list()?)))
+            .flat_map(|(name, image_list)| {
+                (0..image_list.len()).filter_map(move |idx| Some((name, image_list.image(idx)?)))
+            })
     }
-}
 
-#[derive(derive_more::Debug, Copy, Clone)]
-pub struct Image<'a> {
-    pub directory: &'a Path,
-    pub icon_flags: raw::Flags,
-    pub image_data: Option<ImageData<'a>>,
-}
+    /// Returns an iterator over every image in the cache, paired with the name of the icon it
+    /// belongs to. Same as [all_images](Self::all_images).
+    pub fn all_images_with_names(&self) -> impl Iterator<Item = (&'a CStr, Image<'a>)> {
+        self.all_images()
+    }
 
-#[derive(derive_more::Debug, Copy, Clone)]
-pub struct ImageData<'a> {
-    pub image_pixel_data: (), // TODO: what type is this?
-    pub image_meta_data: &'a raw::MetaData,
-    pub image_pixel_data_type: (),
-    pub image_pixel_data_length: (),
-}
+    /// Returns an iterator over the names of all icons in the cache.
+    pub fn names(&self) -> impl Iterator<Item = &'a CStr> {
+        self.raw_icons()
+            .filter_map(|icon| icon.name.str_at(self.bytes).ok())
+    }
 
-fn icon_str_hash(key: impl AsRef<[u8]>) -> u32 {
-    let bytes = key.as_ref();
+    /// Returns the names of the first `n` icons in the cache, in bucket order.
+    ///
+    /// Only as much of the hash table is walked as is needed to find `n` names, so this is O(n)
+    /// rather than proportional to the size of the cache.
+    pub fn take_names(&self, n: usize) -> Vec<&'a CStr> {
+        self.names().take(n).collect()
+    }
 
-    if bytes.is_empty() {
-        return 0;
+    /// Copy every icon and its images out of the cache into an owned map from icon name to
+    /// images, which outlives the cache's bytes.
+    ///
+    /// This allocates for every icon and image in the cache. Icon names are converted to (lossy)
+    /// UTF-8; if two names convert to the same string, only the images of the last one are kept.
+    #[cfg(feature = "std")]
+    pub fn to_owned_map(&self) -> HashMap<String, Vec<OwnedImage>> {
+        self.iter()
+            .map(|icon| {
+                let images = icon.images().map(OwnedImage::from).collect();
+                (icon.name.to_string_lossy().into_owned(), images)
+            })
+            .collect()
     }
 
-    let mut h: u32 = bytes[0] as u32;
-    for &p in &bytes[1..] {
-        h = (h << 5).overflowing_sub(h).0.overflowing_add(p as u32).0;
+    /// Returns the total amount of images across all icons in the cache.
+    ///
+    /// Only the length of each icon's image list is read: no images are resolved.
+    pub fn image_count(&self) -> u64 {
+        self.raw_icons()
+            .filter_map(|icon| icon.image_list.at(self.bytes).ok())
+            .map(|image_list| image_list.n_images.get() as u64)
+            .sum()
     }
 
-    h
-}
+    /// Returns an iterator over the directories referenced by at least one image, e.g. to only
+    /// scan the directories of the theme that actually provide icons.
+    ///
+    /// Directories are returned once each, in the order of the directory list. Images are only
+    /// read for their directory index: directories aren't resolved until they're returned.
+    #[cfg(feature = "std")]
+    pub fn used_directories(&self) -> impl Iterator<Item = &'a Path> {
+        let n_directories = self.directory_list.len() as usize;
+        let mut used = vec![0u64; n_directories.div_ceil(64)];
+
+        let images = self
+            .raw_icons()
+            .filter_map(|icon| icon.image_list.at(self.bytes).ok())
+            .flat_map(|image_list| image_list.elements());
+        for image in images {
+            let idx = image.directory_index.get() as usize;
+            if idx < n_directories {
+                used[idx / 64] |= 1 << (idx % 64);
+            }
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::raw::Offset;
-    use zerocopy::network_endian::U16;
+        let directory_list = self.directory_list;
+        (0..n_directories)
+            .filter(move |idx| used[idx / 64] & (1 << (idx % 64)) != 0)
+            .filter_map(move |idx| directory_list.dir(idx as u32))
+    }
 
-    // The included sample cache file was generated using the gtk-update-icon-cache utility
-    // from my system-installed hicolor theme.
-    static SAMPLE_INDEX_FILE: &[u8] = include_bytes!("../assets/icon-theme.cache");
+    /// Returns an iterator over the names of the icons with an image in `directory`, e.g.
+    /// `48x48/apps`: the inverse of looking up the directories of an icon's images.
+    ///
+    /// Returns an empty iterator if `directory` isn't in the directory list. Each icon is
+    /// returned once, in bucket order, even if it has several images in `directory`.
+    #[cfg(feature = "std")]
+    pub fn icons_in_directory(&self, directory: &Path) -> impl Iterator<Item = &'a CStr> + use<'a> {
+        let bytes = self.bytes;
+        let directory_index = self.directory_list.find(directory);
+
+        self.raw_icons()
+            .filter(move |icon| {
+                let Some(directory_index) = directory_index else {
+                    return false;
+                };
+
+                icon.image_list.at(bytes).is_ok_and(|image_list| {
+                    image_list
+                        .elements()
+                        .iter()
+                        .any(|image| image.directory_index.get() as u32 == directory_index)
+                })
+            })
+            .filter_map(move |icon| icon.name.str_at(bytes).ok())
+    }
 
-    #[test]
-    fn test_find_specific_icon() -> Result<(), Box<dyn Error>> {
-        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+    /// Returns the load factor of the hash table: the amount of icons per bucket.
+    ///
+    /// A load factor well above 1 means that lookups have to walk long chains of icons.
+    /// Computing this walks every bucket of the hash table.
+    pub fn load_factor(&self) -> f64 {
+        let n_buckets = self.hash.n_buckets.get();
+        if n_buckets == 0 {
+            return 0.0;
+        }
 
-        assert_eq!(
-            cache.header,
-            &raw::Header {
-                major_version: U16::new(1),
-                minor_version: U16::new(0),
-                hash: Offset::new(12),
-                directory_list: Offset::new(35812)
-            }
-        );
+        self.raw_icons().count() as f64 / n_buckets as f64
+    }
 
-        assert_eq!(cache.hash.n_buckets, 251);
+    /// Collects the version and a handful of statistics of this cache into an owned
+    /// [CacheSummary], which can be kept around after the cache itself is dropped.
+    ///
+    /// This walks every bucket of the hash table, but doesn't resolve any images.
+    pub fn summary(&self) -> CacheSummary {
+        let mut icon_count = 0;
+        let mut image_count = 0;
+        let mut occupied_buckets = 0;
+
+        for bucket in 0..self.hash.n_buckets.get() {
+            let Some(chain) = self.icon_chain(bucket) else {
+                continue;
+            };
 
-        let icon = cache.icon("mpv").unwrap();
+            occupied_buckets += 1;
 
-        assert_eq!(icon.name.to_str(), Ok("mpv"));
-        assert_eq!(icon.image_list.len(), 5);
+            for icon in chain.iter(self.bytes) {
+                icon_count += 1;
 
-        let image = &icon.image_list.image(0).unwrap();
+                if let Ok(image_list) = icon.image_list.at(self.bytes) {
+                    image_count += image_list.n_images.get() as u64;
+                }
+            }
+        }
 
-        assert_eq!(image.directory.to_str(), Some("scalable/apps"));
-        assert_eq!(
-            image.icon_flags,
-            raw::Flags::new(raw::Flags::HAS_SUFFIX_SVG)
-        );
-        assert!(image.image_data.is_none());
+        let collision_rate = if icon_count == 0 {
+            0.0
+        } else {
+            (icon_count - occupied_buckets) as f64 / icon_count as f64
+        };
 
-        Ok(())
+        CacheSummary {
+            version: self.version(),
+            icon_count,
+            directory_count: self.directory_list.len(),
+            image_count,
+            collision_rate,
+        }
     }
 
-    #[test]
-    fn test_icon_iter() -> Result<(), Box<dyn Error>> {
-        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+    /// Collects statistics on how the icons in this cache are distributed across the buckets of
+    /// its hash table, see [HashStats].
+    ///
+    /// This walks every bucket of the hash table, but doesn't resolve any images.
+    pub fn hash_stats(&self) -> HashStats {
+        let n_buckets = self.hash.n_buckets.get();
+        let mut n_icons = 0;
+        let mut empty_buckets = 0;
+        let mut max_chain_length = 0;
 
-        assert_eq!(cache.iter().count(), 563);
+        for bucket in 0..n_buckets {
+            let Some(chain) = self.icon_chain(bucket) else {
+                empty_buckets += 1;
+                continue;
+            };
 
-        Ok(())
-    }
+            let chain_length = chain.iter(self.bytes).count();
+            n_icons += chain_length;
+            max_chain_length = max_chain_length.max(chain_length);
+        }
 
-    #[test]
-    fn test_image_list_iter() -> Result<(), Box<dyn Error>> {
-        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
-        let icon = cache.icon("mpv").unwrap();
+        let occupied_buckets = n_buckets - empty_buckets;
+        let mean_chain_length = if occupied_buckets == 0 {
+            0.0
+        } else {
+            n_icons as f64 / occupied_buckets as f64
+        };
 
-        let count = icon.image_list.iter().count();
-        assert_eq!(count, 5);
+        HashStats {
+            n_buckets,
+            n_icons,
+            empty_buckets,
+            max_chain_length,
+            mean_chain_length,
+        }
+    }
 
-        Ok(())
+    /// Returns the amount of buckets in the hash table of this cache.
+    pub fn bucket_count(&self) -> u32 {
+        self.hash.n_buckets.get()
     }
 
-    #[test]
-    fn test_directory_list_iter() -> Result<(), Box<dyn Error>> {
-        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
-        let dir_list = cache.directory_list;
+    /// Returns the amount of icons chained in `bucket`, e.g. to find the longest chain.
+    ///
+    /// Returns `0` for empty buckets, and for buckets not smaller than
+    /// [bucket_count](Self::bucket_count).
+    pub fn bucket_len(&self, bucket: u32) -> u32 {
+        if bucket >= self.bucket_count() {
+            return 0;
+        }
 
-        assert_eq!(dir_list.len(), 59);
+        self.icon_chain(bucket)
+            .map_or(0, |icon| icon.iter(self.bytes).count() as u32)
+    }
 
-        assert!(!cache.directory_list.is_empty());
-        assert_eq!(cache.directory_list.iter().count(), 59);
+    /// Returns `true` if any image in the cache refers to a directory index outside of the
+    /// [directory list](Self::directory_list).
+    ///
+    /// This is a common form of corruption, and cheap to check for: no image lists are resolved
+    /// beyond reading their directory indices.
+    pub fn has_invalid_directory_refs(&self) -> bool {
+        self.raw_icons()
+            .any(|icon| self.has_invalid_directory_index(icon))
+    }
+
+    /// Returns the names of all icons that have an image referring to a directory index outside
+    /// of the [directory list](Self::directory_list).
+    ///
+    /// Icons whose name cannot be read are skipped.
+    pub fn invalid_directory_refs(&self) -> impl Iterator<Item = &'a CStr> {
+        self.raw_icons()
+            .filter(|icon| self.has_invalid_directory_index(icon))
+            .filter_map(|icon| icon.name.str_at(self.bytes).ok())
+    }
+
+    fn has_invalid_directory_index(&self, icon: &raw::Icon) -> bool {
+        let Ok(image_list) = icon.image_list.at(self.bytes) else {
+            return false;
+        };
+
+        let n_directories = self.directory_list.len();
+
+        image_list
+            .elements()
+            .iter()
+            .any(|image| image.directory_index.get() as u32 >= n_directories)
+    }
+
+    fn icon_from_raw(&self, icon: &'a raw::Icon) -> Option<Icon<'a>> {
+        let name = icon.name.str_at(self.bytes).ok()?;
+
+        Some(self.icon_with_name(name, icon))
+    }
+
+    fn icon_with_name(&self, name: &'a CStr, icon: &'a raw::Icon) -> Icon<'a> {
+        Icon {
+            name,
+            bytes: self.bytes,
+            directory_list: self.directory_list,
+            raw_icon: icon,
+        }
+    }
+
+    fn raw_icons(&self) -> RawIcons<'a> {
+        RawIcons {
+            cache: *self,
+            bucket: 0,
+            chain: None,
+        }
+    }
+
+    fn icon_chain(&self, bucket: u32) -> Option<&'a raw::Icon> {
+        debug_assert!(bucket < self.hash.n_buckets.get());
+
+        let offset = self.hash.icon[bucket as usize];
+        // A bucket may be empty!
+        if offset.is_null() {
+            return None;
+        }
+
+        offset.at(self.bytes).ok()
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for IconCache<'a> {
+    type Error = IconCacheError;
+
+    /// See [IconCache::new_from_bytes].
+    fn try_from(bytes: &'a [u8]) -> Result<Self, Self::Error> {
+        Self::new_from_bytes(bytes)
+    }
+}
+
+/// Summarizes the cache rather than dumping its hash table and directory list.
+impl core::fmt::Debug for IconCache<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("IconCache")
+            .field("version", &self.version())
+            .field("n_buckets", &self.bucket_count())
+            .field("n_icons", &self.iter().count())
+            .field("n_directories", &self.directory_list.len())
+            .finish()
+    }
+}
+
+impl<'a> IntoIterator for &IconCache<'a> {
+    type Item = Icon<'a>;
+    type IntoIter = IconIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the icons in an [IconCache], see [IconCache::iter].
+#[derive(Debug, Clone)]
+pub struct IconIter<'a> {
+    raw_icons: RawIcons<'a>,
+}
+
+impl<'a> Iterator for IconIter<'a> {
+    type Item = Icon<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cache = self.raw_icons.cache;
+
+        self.raw_icons
+            .by_ref()
+            .find_map(|icon| cache.icon_from_raw(icon))
+    }
+}
+
+/// Iterator over the raw icons in every bucket's chain, in bucket order.
+#[derive(Debug, Clone)]
+struct RawIcons<'a> {
+    cache: IconCache<'a>,
+    /// The next bucket to walk once `chain` runs out
+    bucket: u32,
+    /// The rest of the chain of the current bucket
+    chain: Option<raw::IconChain<'a>>,
+}
+
+impl<'a> Iterator for RawIcons<'a> {
+    type Item = &'a raw::Icon;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(icon) = self.chain.as_mut().and_then(Iterator::next) {
+                return Some(icon);
+            }
+
+            if self.bucket >= self.cache.hash.n_buckets.get() {
+                return None;
+            }
+
+            self.chain = self
+                .cache
+                .icon_chain(self.bucket)
+                .map(|icon| icon.iter(self.cache.bytes));
+            self.bucket += 1;
+        }
+    }
+}
+
+/// Provides access to an [IconCache] constructed from bytes owned by this struct, e.g. a cache read
+/// with `std::fs::read` or decompressed in memory.
+///
+/// To access the icon cache, use [OwnedBytesCache::icon_cache]. To map a cache file into memory
+/// instead of reading it, see `file::OwnedIconCache`.
+#[derive(derive_more::Debug, Clone)]
+pub struct OwnedBytesCache {
+    #[debug(skip)]
+    pub bytes: Vec<u8>,
+}
+
+impl OwnedBytesCache {
+    /// Take ownership of the bytes of a cache. The bytes are only parsed once accessed through
+    /// [icon_cache](Self::icon_cache).
+    ///
+    /// Little-endian caches are converted to big-endian (see [IconCache::swap_byte_order]), so
+    /// they can be read too.
+    pub fn from_vec(mut bytes: Vec<u8>) -> Self {
+        if IconCache::is_byte_swapped(&bytes) {
+            // A cache that can't be converted won't parse either: `icon_cache` reports the error
+            let _ = IconCache::swap_byte_order(&mut bytes);
+        }
+
+        Self { bytes }
+    }
+
+    /// Access the icon cache held by this `OwnedBytesCache`.
+    ///
+    /// Returns an error if the cache could not be parsed.
+    pub fn icon_cache(&self) -> Result<IconCache<'_>, IconCacheError> {
+        IconCache::new_from_bytes(&self.bytes)
+    }
+}
+
+/// Owned summary of an [IconCache], see [IconCache::summary].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CacheSummary {
+    /// `(major, minor)` version of the cache format
+    pub version: (u16, u16),
+    /// Amount of icons in the cache
+    pub icon_count: usize,
+    /// Amount of directories in the cache
+    pub directory_count: u32,
+    /// Amount of images across all icons in the cache
+    pub image_count: u64,
+    /// Fraction of icons that share their bucket with an icon before them in the bucket's chain
+    pub collision_rate: f64,
+}
+
+/// Distribution of icons across the buckets of a cache's hash table, see [IconCache::hash_stats].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HashStats {
+    /// Amount of buckets in the hash table
+    pub n_buckets: u32,
+    /// Amount of icons across all buckets
+    pub n_icons: usize,
+    /// Amount of buckets without any icons
+    pub empty_buckets: u32,
+    /// Length of the longest chain of icons in a single bucket
+    pub max_chain_length: usize,
+    /// Average length of the chains of the non-empty buckets
+    pub mean_chain_length: f64,
+}
+
+/// Errors that can occur while parsing an [IconCache].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum IconCacheError {
+    /// The cache ended before a structure that was expected to be there
+    Truncated,
+    /// The header is malformed, e.g. its offsets point back into the header itself
+    BadHeader,
+    /// The cache has a version this crate does not know how to read
+    UnsupportedVersion { major: u16, minor: u16 },
+    /// An offset points outside of the cache, or to data that could not be parsed
+    InvalidOffset,
+    /// The cache is in little-endian byte order; see [IconCache::swap_byte_order]
+    ByteSwapped,
+}
+
+impl Display for IconCacheError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            IconCacheError::Truncated => write!(f, "icon cache is truncated"),
+            IconCacheError::BadHeader => write!(f, "icon cache has a malformed header"),
+            IconCacheError::UnsupportedVersion { major, minor } => {
+                write!(f, "unsupported icon cache version {major}.{minor}")
+            }
+            IconCacheError::InvalidOffset => write!(f, "icon cache contains an invalid offset"),
+            IconCacheError::ByteSwapped => write!(f, "icon cache is in little-endian byte order"),
+        }
+    }
+}
+
+impl Error for IconCacheError {}
+
+impl<A, S, V> From<ConvertError<A, S, V>> for IconCacheError {
+    fn from(error: ConvertError<A, S, V>) -> Self {
+        match error {
+            ConvertError::Size(_) => IconCacheError::Truncated,
+            ConvertError::Alignment(_) | ConvertError::Validity(_) => IconCacheError::InvalidOffset,
+        }
+    }
+}
+
+impl<Src, Dst: ?Sized> From<SizeError<Src, Dst>> for IconCacheError {
+    fn from(_: SizeError<Src, Dst>) -> Self {
+        IconCacheError::Truncated
+    }
+}
+
+/// List of directories in the icon theme with paths relative to the root of the icon theme.
+#[derive(derive_more::Debug, Copy, Clone)]
+pub struct DirectoryList<'a> {
+    #[debug(skip)]
+    #[allow(unused)] // clippy thinks this is unused but it isn't? maybe because of the Debug
+    bytes: &'a [u8],
+    pub raw_list: &'a raw::DirectoryList,
+}
+
+impl<'a> DirectoryList<'a> {
+    /// Returns the amount of directories in this list
+    #[inline(always)]
+    pub fn len(&self) -> u32 {
+        self.raw_list.n_directories.get()
+    }
+
+    /// Returns `true` if the list is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Access a directory by its index in the list.
+    ///
+    /// Returns `None` if the index larger than the length of the list.
+    #[cfg(feature = "std")]
+    pub fn dir(&self, idx: u32) -> Option<&'a Path> {
+        self.raw_list.get(idx)?.path_at(self.bytes)
+    }
+
+    /// Find the index of `path` in the list, the inverse of [dir](Self::dir).
+    ///
+    /// Returns `None` if the path is not in the list.
+    #[cfg(feature = "std")]
+    pub fn find(&self, path: impl AsRef<Path>) -> Option<u32> {
+        let path = path.as_ref();
+
+        (0..self.len()).find(|&idx| self.dir(idx) == Some(path))
+    }
+
+    /// Access a directory by its index in the list, and parse its size, scale and context.
+    ///
+    /// Returns `None` if the index larger than the length of the list.
+    #[cfg(feature = "std")]
+    pub fn info(&self, idx: u32) -> Option<DirectoryInfo<'a>> {
+        self.dir(idx).map(DirectoryInfo::parse)
+    }
+
+    /// Returns an iterator over the directory list.
+    ///
+    /// The iterator can be reversed to visit the last-listed directories first.
+    #[cfg(feature = "std")]
+    pub fn iter(&self) -> DirectoryIter<'a> {
+        DirectoryIter {
+            directory_list: *self,
+            indices: 0..self.len(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> IntoIterator for &DirectoryList<'a> {
+    type Item = &'a Path;
+    type IntoIter = DirectoryIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the directories in a [DirectoryList], see [DirectoryList::iter].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct DirectoryIter<'a> {
+    directory_list: DirectoryList<'a>,
+    /// Indices of the directories that haven't been visited from either end
+    indices: Range<u32>,
+}
+
+#[cfg(feature = "std")]
+impl<'a> Iterator for DirectoryIter<'a> {
+    type Item = &'a Path;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let directory_list = self.directory_list;
+
+        self.indices.find_map(|idx| directory_list.dir(idx))
+    }
+}
+
+#[cfg(feature = "std")]
+impl DoubleEndedIterator for DirectoryIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let directory_list = self.directory_list;
+
+        self.indices
+            .by_ref()
+            .rev()
+            .find_map(|idx| directory_list.dir(idx))
+    }
+}
+
+/// Size, scale and context of a directory in the icon theme, parsed from its path.
+///
+/// Directories conventionally follow the pattern `<size>[@<scale>x]/<context>`, e.g. `48x48/apps`,
+/// `16x16@2x/mimetypes` or `scalable/apps`.
+#[cfg(feature = "std")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DirectoryInfo<'a> {
+    /// Pixel size of the images in this directory, or `None` if the directory is not of the form
+    /// `<size>x<size>`, such as `scalable`
+    pub size: Option<u32>,
+    /// Scale factor of the images in this directory, `1` unless the directory has a `@<scale>x` suffix
+    pub scale: u32,
+    /// Context of the directory, e.g. `apps` or `mimetypes`, if any
+    pub context: Option<&'a Path>,
+}
+
+#[cfg(feature = "std")]
+impl<'a> DirectoryInfo<'a> {
+    /// Parse a directory path relative to the icon theme's root.
+    pub fn parse(directory: &'a Path) -> Self {
+        let mut components = directory.components();
+        let first = components
+            .next()
+            .map(|component| component.as_os_str().as_bytes())
+            .unwrap_or_default();
+
+        let context = Some(components.as_path()).filter(|context| !context.as_os_str().is_empty());
+
+        let parse_u32 = |bytes: &[u8]| core::str::from_utf8(bytes).ok()?.parse::<u32>().ok();
+
+        let (size, scale) = match first.iter().rposition(|&b| b == b'@') {
+            Some(at) => {
+                let scale = first[at + 1..].strip_suffix(b"x").and_then(parse_u32);
+                (&first[..at], scale)
+            }
+            None => (first, None),
+        };
+
+        // `<width>x<height>`: icon theme directories are square, so the width is the size
+        let size = size.iter().position(|&b| b == b'x').and_then(|x| {
+            parse_u32(&size[x + 1..])?;
+            parse_u32(&size[..x])
+        });
+
+        DirectoryInfo {
+            size,
+            scale: scale.unwrap_or(1),
+            context,
+        }
+    }
+}
+
+/// An icon, identified by its name, and the list of images provided by the icon theme for this icon.
+///
+/// The image list is only resolved when asked for with [image_list](Icon::image_list), so that
+/// icons can be looked up or iterated over cheaply when only their names are needed.
+#[derive(derive_more::Debug, Copy, Clone)]
+pub struct Icon<'a> {
+    pub name: &'a CStr,
+    #[debug(skip)]
+    bytes: &'a [u8],
+    /// The directory list of the cache this icon belongs to, passed on to its image list
+    #[debug(skip)]
+    directory_list: DirectoryList<'a>,
+    pub raw_icon: &'a raw::Icon,
+}
+
+/// Icons are equal if they have the same name.
+impl PartialEq for Icon<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for Icon<'_> {}
+
+impl<'a> Icon<'a> {
+    /// Resolves the list of images provided for this icon.
+    ///
+    /// Returns `None` if the image list could not be parsed, e.g. because it lies outside of the
+    /// cache.
+    pub fn image_list(&self) -> Option<ImageList<'a>> {
+        ImageList::from_icon(self.raw_icon, self.bytes, self.directory_list)
+    }
+
+    /// Returns an iterator over the images of this icon, which is empty if the image list could
+    /// not be parsed.
+    fn images(&self) -> impl Iterator<Item = Image<'a>> + use<'a> {
+        self.image_list()
+            .into_iter()
+            .flat_map(|image_list| image_list.iter())
+    }
+
+    /// Returns the flags of every image of this icon combined, e.g. to check whether the icon is
+    /// available as an SVG in any directory.
+    pub fn combined_flags(&self) -> raw::Flags {
+        self.images().fold(raw::Flags::default(), |flags, image| {
+            flags | image.icon_flags
+        })
+    }
+
+    /// Returns the first image of this icon available in every format set in `flags`, e.g.
+    /// `Flags::new(Flags::HAS_SUFFIX_SVG)` for an SVG renderer, or `None` if there is none.
+    ///
+    /// Unlike `best_image_for_size`, the size of the image isn't considered: images are checked
+    /// in the order of the image list.
+    pub fn image_with_format(&self, flags: raw::Flags) -> Option<Image<'a>> {
+        self.images().find(|image| image.icon_flags.contains(flags))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> Icon<'a> {
+    /// Resolves the file paths of every image of this icon in the theme at `theme_root`, named
+    /// after this icon's [name](Self::name), e.g. `<theme_root>/scalable/apps/mpv.svg`.
+    ///
+    /// An image available in multiple formats has a path for each of them, see
+    /// [Image::file_paths]. Images without any suffix flag have no paths.
+    pub fn resolve_all_paths(&self, theme_root: &Path) -> Vec<PathBuf> {
+        self.images()
+            .flat_map(|image| image.file_paths(theme_root, self.name))
+            .collect()
+    }
+
+    /// Returns every image of this icon with a score of how well it matches the requested `size`
+    /// at `scale`, sorted best-first. Lower scores are better; images with equal scores keep
+    /// the order of the image list.
+    ///
+    /// Scores are based on the directory the image is in (see [DirectoryInfo]), comparing the
+    /// directory's size times its scale against `size` times `scale` in pixels:
+    ///
+    /// * an exact match scores `0`,
+    /// * a scalable image (any directory without a size) scores `1`,
+    /// * an image `d` pixels larger scores `2 * d`, and one `d` pixels smaller scores `2 * d + 1`:
+    ///   scaling down is preferred over scaling up.
+    pub fn ranked_images_for_size(&self, size: u16, scale: u16) -> Vec<(Image<'a>, i32)> {
+        let target = size as i64 * scale as i64;
+
+        let mut images = self
+            .images()
+            .map(|image| {
+                let info = DirectoryInfo::parse(image.directory);
+                let score = match info.size {
+                    None => 1,
+                    Some(size) => {
+                        let pixels = size as i64 * info.scale as i64;
+                        let distance = 2 * (pixels - target).abs();
+                        if pixels < target {
+                            distance + 1
+                        } else {
+                            distance
+                        }
+                    }
+                };
+
+                (image, score.min(i32::MAX as i64) as i32)
+            })
+            .collect::<Vec<_>>();
+
+        images.sort_by_key(|&(_, score)| score);
+        images
+    }
+
+    /// Returns the image of this icon that best matches the requested `size` at `scale`, or `None`
+    /// if the icon has no images.
+    ///
+    /// See [ranked_images_for_size](Self::ranked_images_for_size) for how images are compared.
+    pub fn best_image_for_size(&self, size: u16, scale: u16) -> Option<Image<'a>> {
+        let (image, _) = self
+            .ranked_images_for_size(size, scale)
+            .into_iter()
+            .next()?;
+        Some(image)
+    }
+
+    /// Returns the parsed directory of every image of this icon alongside the formats it is
+    /// available in.
+    pub fn size_format_table(&self) -> Vec<(DirectoryInfo<'a>, raw::Flags)> {
+        self.images()
+            .map(|image| (DirectoryInfo::parse(image.directory), image.icon_flags))
+            .collect()
+    }
+}
+
+#[derive(derive_more::Debug, Copy, Clone)]
+pub struct ImageList<'a> {
+    #[debug(skip)]
+    bytes: &'a [u8],
+    /// The directory list of the cache this image list belongs to, used to resolve the
+    /// directories of images
+    #[debug(skip)]
+    directory_list: DirectoryList<'a>,
+    pub raw_list: &'a raw::ImageList,
+}
+
+impl<'a> ImageList<'a> {
+    fn from_icon(
+        icon: &raw::Icon,
+        bytes: &'a [u8],
+        directory_list: DirectoryList<'a>,
+    ) -> Option<ImageList<'a>> {
+        Some(Self {
+            bytes,
+            directory_list,
+            raw_list: icon.image_list.at(bytes).ok()?,
+        })
+    }
+
+    /// Returns the amount of images in this list
+    pub fn len(&self) -> u32 {
+        self.raw_list.n_images.get()
+    }
+
+    /// Returns `true` if the list is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Access an image by its index in the list.
+    ///
+    /// Returns `None` if the index larger than the length of the list, or if the image data
+    /// failed to parse.
+    pub fn image(&self, idx: u32) -> Option<Image<'a>> {
+        self.resolve(self.raw_list.get(idx)?)
+    }
+
+    fn resolve(&self, raw_image: &raw::Image) -> Option<Image<'a>> {
+        #[cfg(feature = "std")]
+        let directory = self
+            .directory_list
+            .dir(raw_image.directory_index.get() as u32)?;
+        #[cfg(not(feature = "std"))]
+        if raw_image.directory_index.get() as u32 >= self.directory_list.len() {
+            return None;
+        }
+
+        let icon_flags = raw_image.icon_flags;
+
+        let mut image_data = None;
+
+        if raw_image.image_data.offset != 0 {
+            let raw_image_data = raw_image.image_data.at(self.bytes).ok()?;
+            let &raw::ImageData {
+                image_pixel_data,
+                image_meta_data,
+                ..
+            } = raw_image_data;
+
+            let image_meta_data = if image_meta_data.is_null() {
+                None
+            } else {
+                Some(MetaData {
+                    bytes: self.bytes,
+                    raw_meta_data: image_meta_data.at(self.bytes).ok()?,
+                })
+            };
+
+            let (image_pixel_data_type, image_pixel_data_length) =
+                pixel_data_header(self.bytes, image_pixel_data).unwrap_or_default();
+
+            image_data = Some(ImageData {
+                bytes: self.bytes,
+                raw_image_data,
+                image_pixel_data: *image_pixel_data.at(self.bytes).ok()?,
+                image_meta_data,
+                image_pixel_data_type,
+                image_pixel_data_length,
+            });
+        }
+
+        Some(Image {
+            #[cfg(feature = "std")]
+            directory,
+            icon_flags,
+            image_data,
+        })
+    }
+
+    /// Returns an iterator over the image list.
+    ///
+    /// Images that fail to parse (see [image](Self::image)) are skipped. The length of the
+    /// iterator counts every entry of the list without parsing it, so on a corrupt cache the
+    /// iterator may yield fewer images than its length claims. Entries the list claims to have but
+    /// which lie past the end of the cache are left out of the length.
+    pub fn iter(&self) -> ImageIter<'a> {
+        ImageIter {
+            image_list: *self,
+            raw_images: self.raw_list.elements().iter(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> ImageList<'a> {
+    /// Returns an iterator over the images in a directory for which `filter` returns `true`.
+    pub fn images_in<'b>(
+        &self,
+        filter: impl Fn(&Path) -> bool + 'b,
+    ) -> impl Iterator<Item = Image<'a>> + 'b
+    where
+        'a: 'b,
+    {
+        self.iter().filter(move |image| filter(image.directory))
+    }
+
+    /// Returns the images in this list sorted by the size of their directory, smallest first.
+    ///
+    /// Sizes are parsed from the directory names (see [DirectoryInfo]); images of equal size are
+    /// sorted by scale. Scalable images, and any others in a directory without a size, come last.
+    /// Otherwise, images keep the order of the list.
+    pub fn sorted_by_size(&self) -> Vec<Image<'a>> {
+        let mut images = self.iter().collect::<Vec<_>>();
+
+        images.sort_by_key(|image| {
+            let info = DirectoryInfo::parse(image.directory);
+            (info.size.is_none(), info.size, info.scale)
+        });
+
+        images
+    }
+
+    /// Groups the images in this list by the size of their directory, e.g. to present the sizes
+    /// an icon is available in.
+    ///
+    /// Sizes are parsed from the directory names (see [DirectoryInfo]). Scalable images, and any
+    /// others in a directory without a size, are grouped under `None`, which comes first. Within
+    /// a group, images keep the order of the list.
+    pub fn by_size(&self) -> BTreeMap<Option<u32>, Vec<Image<'a>>> {
+        let mut groups = BTreeMap::<_, Vec<_>>::new();
+
+        for image in self.iter() {
+            let info = DirectoryInfo::parse(image.directory);
+            groups.entry(info.size).or_default().push(image);
+        }
+
+        groups
+    }
+
+    /// Returns an iterator over the images in `directory`, e.g. `scalable/apps`.
+    pub fn images_in_directory<'b>(
+        &self,
+        directory: &'b Path,
+    ) -> impl Iterator<Item = Image<'a>> + 'b
+    where
+        'a: 'b,
+    {
+        self.images_in(move |dir| dir == directory)
+    }
+}
+
+impl<'a> IntoIterator for &ImageList<'a> {
+    type Item = Image<'a>;
+    type IntoIter = ImageIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the images in an [ImageList], see [ImageList::iter].
+#[derive(Debug, Clone)]
+pub struct ImageIter<'a> {
+    image_list: ImageList<'a>,
+    raw_images: core::slice::Iter<'a, raw::Image>,
+}
+
+impl<'a> Iterator for ImageIter<'a> {
+    type Item = Image<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.raw_images
+            .by_ref()
+            .find_map(|raw_image| self.image_list.resolve(raw_image))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.raw_images.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for ImageIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.raw_images
+            .by_ref()
+            .rev()
+            .find_map(|raw_image| self.image_list.resolve(raw_image))
+    }
+}
+
+/// The length counts every remaining entry, including any that fail to parse and are skipped,
+/// see [ImageList::iter].
+impl ExactSizeIterator for ImageIter<'_> {}
+
+#[derive(derive_more::Debug, Copy, Clone)]
+pub struct Image<'a> {
+    /// Directory the image is in, relative to the root of the icon theme
+    #[cfg(feature = "std")]
+    pub directory: &'a Path,
+    pub icon_flags: raw::Flags,
+    pub image_data: Option<ImageData<'a>>,
+}
+
+/// Images are equal if they are in the same directory, have the same flags, and either both or
+/// neither carry image data. The image data itself isn't compared.
+impl PartialEq for Image<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        #[cfg(feature = "std")]
+        if self.directory != other.directory {
+            return false;
+        }
+
+        self.icon_flags == other.icon_flags
+            && self.image_data.is_some() == other.image_data.is_some()
+    }
+}
+
+impl Eq for Image<'_> {}
+
+impl Image<'_> {
+    /// Returns `true` if the image's flags agree with the image data it carries.
+    ///
+    /// `gtk-update-icon-cache` only embeds pixel data for raster images, and only stores meta data
+    /// read from an accompanying `.icon` file. An image must also be backed by at least one file.
+    /// An image violating any of these rules indicates an inconsistently generated cache.
+    pub fn flags_consistent(&self) -> bool {
+        let flags = self.icon_flags;
+
+        if flags.suffixes().next().is_none() && !flags.has_icon_file() {
+            return false;
+        }
+
+        let Some(image_data) = self.image_data else {
+            return true;
+        };
+
+        let has_pixel_data = !image_data.raw_image_data.image_pixel_data.is_null();
+        if has_pixel_data && !(flags.has_suffix_png() || flags.has_suffix_xpm()) {
+            return false;
+        }
+
+        if image_data.image_meta_data.is_some() && !flags.has_icon_file() {
+            return false;
+        }
+
+        true
+    }
+
+    /// Returns the embedded rectangle of the image: the region text or emblems can be drawn over.
+    ///
+    /// Returns `None` if the image has no meta data, or its meta data has no embedded rectangle.
+    /// See [MetaData::embedded_rect].
+    pub fn embedded_rect(&self) -> Option<raw::EmbeddedRect> {
+        self.image_data?.image_meta_data?.embedded_rect().copied()
+    }
+
+    /// Returns the candidate paths of the file backing this image, for an icon named `icon_name`
+    /// in the theme at `theme_root`, e.g. `<theme_root>/scalable/apps/mpv.svg`.
+    ///
+    /// A path is returned for every suffix set in the image's flags, in order of preference (see
+    /// [raw::Flags::suffixes]).
+    #[cfg(feature = "std")]
+    pub fn file_paths<'n>(
+        &self,
+        theme_root: &Path,
+        icon_name: &'n CStr,
+    ) -> impl Iterator<Item = PathBuf> + use<'n> {
+        let directory = theme_root.join(self.directory);
+        let icon_name = OsStr::from_bytes(icon_name.to_bytes());
+
+        self.icon_flags.suffixes().map(move |suffix| {
+            let mut file_name = icon_name.to_owned();
+            file_name.push(".");
+            file_name.push(suffix);
+
+            directory.join(file_name)
+        })
+    }
+}
+
+#[derive(derive_more::Debug, Copy, Clone)]
+pub struct ImageData<'a> {
+    #[debug(skip)]
+    bytes: &'a [u8],
+    pub raw_image_data: &'a raw::ImageData,
+    pub image_pixel_data: (), // TODO: what type is this?
+    pub image_meta_data: Option<MetaData<'a>>,
+    /// Type of the embedded pixel data, or `0` if the image has none. See
+    /// [pixel_data_type](Self::pixel_data_type).
+    pub image_pixel_data_type: u32,
+    /// Length of the embedded pixel data in bytes, or `0` if the image has none. See
+    /// [pixel_data_len](Self::pixel_data_len).
+    pub image_pixel_data_length: u32,
+}
+
+impl<'a> ImageData<'a> {
+    /// Returns the pixel data embedded in the cache for this image, or `None` if it has none.
+    ///
+    /// `gtk-update-icon-cache` embeds pixel data as a serialized `GdkPixdata`: a header with the
+    /// image's dimensions and encoding, followed by its pixels. With the `image` feature, it can
+    /// be decoded using `to_rgba`.
+    pub fn pixel_bytes(&self) -> Option<&'a [u8]> {
+        let (_, pixel_bytes) = self.pixel_data()?;
+        Some(pixel_bytes)
+    }
+
+    /// Returns the type of the embedded pixel data, or `None` if the image has none.
+    ///
+    /// `gtk-update-icon-cache` only writes type `0`: a serialized `GdkPixdata`. The encoding of
+    /// the pixels is stored in the `GdkPixdata` header instead.
+    pub fn pixel_data_type(&self) -> Option<u32> {
+        let (pixel_data_type, _) =
+            pixel_data_header(self.bytes, self.raw_image_data.image_pixel_data)?;
+        Some(pixel_data_type)
+    }
+
+    /// Returns the length in bytes of the embedded pixel data, or `None` if the image has none.
+    ///
+    /// Unlike [pixel_bytes](Self::pixel_bytes), this doesn't check that the cache actually holds
+    /// that many bytes.
+    pub fn pixel_data_len(&self) -> Option<u32> {
+        let (_, length) = pixel_data_header(self.bytes, self.raw_image_data.image_pixel_data)?;
+        Some(length)
+    }
+
+    /// Returns the type and bytes of the pixel data.
+    ///
+    /// The pixel data offset points at the type and length of the pixel data, followed by the
+    /// pixel data itself.
+    fn pixel_data(&self) -> Option<(u32, &'a [u8])> {
+        let offset = self.raw_image_data.image_pixel_data;
+        let (pixel_data_type, length) = pixel_data_header(self.bytes, offset)?;
+
+        let start = offset.offset.get() as usize + 2 * size_of::<U32>();
+        let pixel_data = self.bytes.get(start..)?.get(..length as usize)?;
+
+        Some((pixel_data_type, pixel_data))
+    }
+}
+
+/// Read the type and length of the pixel data at `offset`, or `None` if the offset is null or
+/// out of bounds.
+fn pixel_data_header(bytes: &[u8], offset: raw::Offset<U32, ()>) -> Option<(u32, u32)> {
+    if offset.is_null() {
+        return None;
+    }
+
+    let bytes = bytes.get(offset.offset.get() as usize..)?;
+    let ([pixel_data_type, length], _) = <[U32; 2]>::ref_from_prefix(bytes).ok()?;
+
+    Some((pixel_data_type.get(), length.get()))
+}
+
+/// An owned copy of an [Image], see [IconCache::to_owned_map].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OwnedImage {
+    /// Directory the image is in, relative to the root of the icon theme
+    pub directory: PathBuf,
+    pub icon_flags: raw::Flags,
+    /// Meta data of the image, if it has any. Embedded pixel data is not copied.
+    pub meta_data: Option<OwnedMetaData>,
+}
+
+#[cfg(feature = "std")]
+impl From<Image<'_>> for OwnedImage {
+    fn from(image: Image<'_>) -> Self {
+        Self {
+            directory: image.directory.to_path_buf(),
+            icon_flags: image.icon_flags,
+            meta_data: image
+                .image_data
+                .and_then(|image_data| image_data.image_meta_data)
+                .map(OwnedMetaData::from),
+        }
+    }
+}
+
+/// An owned copy of the [MetaData] of an image.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct OwnedMetaData {
+    pub embedded_rect: Option<raw::EmbeddedRect>,
+    /// `(x, y)` coordinates of the attach points
+    pub attach_points: Vec<(u16, u16)>,
+    /// `(language, display name)` pairs, converted to (lossy) UTF-8
+    pub display_names: Vec<(String, String)>,
+}
+
+#[cfg(feature = "std")]
+impl From<MetaData<'_>> for OwnedMetaData {
+    fn from(meta_data: MetaData<'_>) -> Self {
+        Self {
+            embedded_rect: meta_data.embedded_rect().copied(),
+            attach_points: meta_data
+                .attach_points()
+                .map(|attach_points| attach_points.iter().collect())
+                .unwrap_or_default(),
+            display_names: meta_data
+                .display_names()
+                .map(|display_names| {
+                    display_names
+                        .iter()
+                        .map(|(lang, name)| {
+                            (
+                                lang.to_string_lossy().into_owned(),
+                                name.to_string_lossy().into_owned(),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Additional information attached to an image: its embedded rectangle, attach points and
+/// localized display names.
+#[derive(derive_more::Debug, Copy, Clone)]
+pub struct MetaData<'a> {
+    #[debug(skip)]
+    bytes: &'a [u8],
+    pub raw_meta_data: &'a raw::MetaData,
+}
+
+impl<'a> MetaData<'a> {
+    /// Returns the embedded rectangle of the image, or `None` if it has none.
+    pub fn embedded_rect(&self) -> Option<&'a raw::EmbeddedRect> {
+        let offset = self.raw_meta_data.embedded_rect;
+        if offset.is_null() {
+            return None;
+        }
+
+        offset.at(self.bytes).ok()
+    }
+
+    /// Returns the list of attach points of the image, or `None` if it has none.
+    pub fn attach_points(&self) -> Option<AttachPointList<'a>> {
+        let offset = self.raw_meta_data.attach_point_list;
+        if offset.is_null() {
+            return None;
+        }
+
+        Some(AttachPointList {
+            raw_list: offset.at(self.bytes).ok()?,
+        })
+    }
+
+    /// Returns the list of localized display names of the image, or `None` if it has none.
+    pub fn display_names(&self) -> Option<DisplayNameList<'a>> {
+        let offset = self.raw_meta_data.display_name_list;
+        if offset.is_null() {
+            return None;
+        }
+
+        Some(DisplayNameList {
+            bytes: self.bytes,
+            raw_list: offset.at(self.bytes).ok()?,
+        })
+    }
+}
+
+/// List of points at which emblems or other overlays may be attached to an image.
+#[derive(Debug, Copy, Clone)]
+pub struct AttachPointList<'a> {
+    pub raw_list: &'a raw::AttachPointList,
+}
+
+impl AttachPointList<'_> {
+    /// Returns the amount of attach points in this list
+    pub fn len(&self) -> u32 {
+        self.raw_list.n_attach_points.get()
+    }
+
+    /// Returns `true` if the list is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Access an attach point by its index in the list, as `(x, y)` coordinates.
+    ///
+    /// Returns `None` if the index larger than the length of the list.
+    pub fn point(&self, idx: u32) -> Option<(u16, u16)> {
+        let point = self.raw_list.get(idx)?;
+        Some((point.x.get(), point.y.get()))
+    }
+
+    /// Returns an iterator over the `(x, y)` coordinates of the attach points
+    pub fn iter(&self) -> impl Iterator<Item = (u16, u16)> {
+        (0..self.len()).filter_map(|idx| self.point(idx))
+    }
+}
+
+/// List of an image's display names, per language.
+#[derive(derive_more::Debug, Copy, Clone)]
+pub struct DisplayNameList<'a> {
+    #[debug(skip)]
+    bytes: &'a [u8],
+    pub raw_list: &'a raw::DisplayNameList,
+}
+
+impl<'a> DisplayNameList<'a> {
+    /// Returns the amount of display names in this list
+    pub fn len(&self) -> u32 {
+        self.raw_list.n_display_names.get()
+    }
+
+    /// Returns `true` if the list is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the `(language, display name)` pairs in this list.
+    ///
+    /// Entries that fail to parse are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a CStr, &'a CStr)> {
+        self.raw_list.elements().iter().filter_map(|display_name| {
+            Some((
+                display_name.display_lang.str_at(self.bytes).ok()?,
+                display_name.display_name.str_at(self.bytes).ok()?,
+            ))
+        })
+    }
+
+    /// Look up the display name for the language `lang`, e.g. `de`.
+    ///
+    /// If there is no display name for `lang`, the display name with an empty language is
+    /// returned, if any.
+    pub fn lookup(&self, lang: &str) -> Option<&'a CStr> {
+        let mut fallback = None;
+
+        for (display_lang, display_name) in self.iter() {
+            if display_lang.to_bytes() == lang.as_bytes() {
+                return Some(display_name);
+            }
+
+            if display_lang.is_empty() && fallback.is_none() {
+                fallback = Some(display_name);
+            }
+        }
+
+        fallback
+    }
+}
+
+/// Hash an icon name the way GTK does to pick its bucket in the hash table of a cache: the bucket
+/// is the hash modulo the amount of buckets.
+///
+/// The hash is computed over the raw bytes of the name, not its characters, with GTK's wrapping
+/// `h = (h << 5) - h + byte`. Like GTK, which reads names as `signed char`, bytes past ASCII are
+/// sign-extended first: this matters for names in UTF-8 or any other non-ASCII encoding. Hashing
+/// stops at the first nul byte, if any.
+///
+/// Use this to build caches GTK can read, or see [IconCache::icon_by_hash] to look up an icon
+/// using a precomputed hash.
+pub fn icon_str_hash(key: impl AsRef<[u8]>) -> u32 {
+    let mut bytes = key
+        .as_ref()
+        .iter()
+        .take_while(|&&byte| byte != 0)
+        .map(|&byte| byte as i8 as u32);
+
+    let Some(first) = bytes.next() else {
+        return 0;
+    };
+
+    bytes.fold(first, |h, byte| (h << 5).wrapping_sub(h).wrapping_add(byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use crate::raw::Offset;
+    use zerocopy::network_endian::U16;
+
+    // The included sample cache file was generated using the gtk-update-icon-cache utility
+    // from my system-installed hicolor theme.
+    pub(crate) static SAMPLE_INDEX_FILE: &[u8] = include_bytes!("../assets/icon-theme.cache");
+
+    /// Offset of `ptr` into [SAMPLE_INDEX_FILE].
+    pub(crate) fn offset_of<T: ?Sized>(ptr: &T) -> usize {
+        ptr as *const T as *const u8 as usize - SAMPLE_INDEX_FILE.as_ptr() as usize
+    }
+
+    /// Byte buffer for hand-assembling caches with contents the sample cache doesn't have.
+    #[derive(Default)]
+    struct Synth(Vec<u8>);
+
+    impl Synth {
+        fn pos(&self) -> u32 {
+            self.0.len() as u32
+        }
+
+        fn u8(&mut self, value: u8) -> &mut Self {
+            self.0.push(value);
+            self
+        }
+
+        fn u16(&mut self, value: u16) -> &mut Self {
+            self.0.extend_from_slice(&value.to_be_bytes());
+            self
+        }
+
+        fn u32(&mut self, value: u32) -> &mut Self {
+            self.0.extend_from_slice(&value.to_be_bytes());
+            self
+        }
+
+        fn str(&mut self, value: &[u8]) -> &mut Self {
+            self.0.extend_from_slice(value);
+            self.0.push(0);
+            self
+        }
+
+        fn set_u32(&mut self, at: u32, value: u32) {
+            let at = at as usize;
+            self.0[at..at + 4].copy_from_slice(&value.to_be_bytes());
+        }
+    }
+
+    /// A cache with a single icon `test`, whose only image (in `48x48/apps`) carries `pixel_data`
+    /// as its embedded pixel data.
+    pub(crate) fn synthetic_cache_with_pixel_data(pixel_data: &[u8]) -> Vec<u8> {
+        let mut s = Synth::default();
+
+        // header
+        s.u16(1).u16(0).u32(12).u32(0);
+        // hash with one bucket
+        s.u32(1).u32(20);
+        // icon
+        s.u32(0xFFFFFFFF).u32(32).u32(40);
+        s.str(b"test").u16(0).u8(0);
+
+        // image list
+        s.u32(1).u16(0).u16(4).u32(52);
+        // image data, directly followed by the pixel data's type, length and bytes
+        s.u32(60).u32(0);
+        s.u32(0).u32(pixel_data.len() as u32);
+        s.0.extend_from_slice(pixel_data);
+
+        let dir = s.pos();
+        s.str(b"48x48/apps");
+        let dir_list = s.pos();
+        s.u32(1).u32(dir);
+        s.set_u32(8, dir_list);
+
+        s.0
+    }
+
+    /// A cache with a single icon `test`, whose only image (in `48x48/apps`) carries meta data
+    /// with an embedded rect, two attach points and two display names.
+    fn synthetic_cache_with_meta_data() -> Vec<u8> {
+        let mut s = Synth::default();
+
+        // header
+        s.u16(1).u16(0).u32(12).u32(0);
+        // hash with one bucket
+        s.u32(1).u32(20);
+        // icon
+        s.u32(0xFFFFFFFF).u32(0).u32(0);
+
+        let name = s.pos();
+        s.str(b"test");
+        s.set_u32(24, name);
+
+        let dir = s.pos();
+        s.str(b"48x48/apps");
+        let dir_list = s.pos();
+        s.u32(1).u32(dir);
+        s.set_u32(8, dir_list);
+
+        let image_list = s.pos();
+        s.u32(1).u16(0).u16(4 | 8).u32(image_list + 12);
+        s.set_u32(28, image_list);
+
+        // image data: no pixel data, only meta data
+        let meta_data = image_list + 12 + 16;
+        s.u32(0).u32(meta_data).u32(0).u32(0);
+        s.u32(meta_data + 12)
+            .u32(meta_data + 20)
+            .u32(meta_data + 32);
+        // embedded rect
+        s.u16(1).u16(2).u16(30).u16(40);
+        // attach points
+        s.u32(2).u16(5).u16(6).u16(7).u16(8);
+        // display names
+        let display_names = s.pos();
+        s.u32(2).u32(0).u32(0).u32(0).u32(0);
+        for (i, str) in [&b"de"[..], "Prüfung".as_bytes(), b"", b"Test"]
+            .iter()
+            .enumerate()
+        {
+            let pos = s.pos();
+            s.str(str);
+            s.set_u32(display_names + 4 + 4 * i as u32, pos);
+        }
+
+        s.0
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_find_specific_icon() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        assert_eq!(
+            cache.header,
+            &raw::Header {
+                major_version: U16::new(1),
+                minor_version: U16::new(0),
+                hash: Offset::new(12),
+                directory_list: Offset::new(35812)
+            }
+        );
+
+        assert_eq!(cache.hash.n_buckets, 251);
+
+        let icon = cache.icon("mpv").unwrap();
+
+        assert_eq!(icon.name.to_str(), Ok("mpv"));
+        assert_eq!(icon.image_list().unwrap().len(), 5);
+
+        let image = &icon.image_list().unwrap().image(0).unwrap();
+
+        assert_eq!(image.directory.to_str(), Some("scalable/apps"));
+        assert_eq!(
+            image.icon_flags,
+            raw::Flags::new(raw::Flags::HAS_SUFFIX_SVG)
+        );
+        assert!(image.image_data.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        assert_eq!(
+            IconCache::new_from_bytes(&SAMPLE_INDEX_FILE[..8]).unwrap_err(),
+            IconCacheError::Truncated
+        );
+
+        let mut bytes = SAMPLE_INDEX_FILE.to_vec();
+        bytes[4..8].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert_eq!(
+            IconCache::new_from_bytes(&bytes).unwrap_err(),
+            IconCacheError::InvalidOffset
+        );
+
+        bytes[4..8].copy_from_slice(&4u32.to_be_bytes());
+        assert_eq!(
+            IconCache::new_from_bytes(&bytes).unwrap_err(),
+            IconCacheError::BadHeader
+        );
+    }
+
+    #[test]
+    fn test_new_from_bytes_at() {
+        let mut bytes = b"some junk before the cache".to_vec();
+        let start = bytes.len();
+        bytes.extend_from_slice(SAMPLE_INDEX_FILE);
+        bytes.extend_from_slice(b"and some after");
+
+        let cache = IconCache::new_from_bytes_at(&bytes, start).unwrap();
+        assert_eq!(cache.version(), (1, 0));
+        assert!(cache.icon("mpv").is_some());
+        assert_eq!(cache.iter().count(), 563);
+
+        assert!(IconCache::new_from_bytes_at(&bytes, 0).is_err());
+        assert_eq!(
+            IconCache::new_from_bytes_at(&bytes, bytes.len() + 1).unwrap_err(),
+            IconCacheError::Truncated
+        );
+    }
+
+    #[test]
+    fn test_truncated_inputs() {
+        assert_eq!(
+            IconCache::new_from_bytes(&[]).unwrap_err(),
+            IconCacheError::Truncated
+        );
+        assert_eq!(
+            IconCache::new_from_bytes(&[0]).unwrap_err(),
+            IconCacheError::Truncated
+        );
+
+        // A header whose hash table and directory list would start right after it
+        let mut s = Synth::default();
+        s.u16(1).u16(0).u32(12).u32(12);
+        assert_eq!(
+            IconCache::new_from_bytes(&s.0).unwrap_err(),
+            IconCacheError::Truncated
+        );
+
+        // A header whose offsets point past its end
+        let mut s = Synth::default();
+        s.u16(1).u16(0).u32(16).u32(16);
+        assert_eq!(
+            IconCache::new_from_bytes(&s.0).unwrap_err(),
+            IconCacheError::InvalidOffset
+        );
+    }
+
+    #[test]
+    fn test_version() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+        assert_eq!(cache.version(), (1, 0));
+
+        let mut bytes = SAMPLE_INDEX_FILE.to_vec();
+        bytes[2..4].copy_from_slice(&3u16.to_be_bytes());
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+        assert_eq!(cache.version(), (1, 3));
+
+        bytes[0..2].copy_from_slice(&2u16.to_be_bytes());
+        assert_eq!(
+            IconCache::new_from_bytes(&bytes).unwrap_err(),
+            IconCacheError::UnsupportedVersion { major: 2, minor: 3 }
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn truncated_cache_does_not_panic() {
+        for len in (0..SAMPLE_INDEX_FILE.len()).step_by(7) {
+            let Ok(cache) = IconCache::new_from_bytes(&SAMPLE_INDEX_FILE[..len]) else {
+                continue;
+            };
+
+            let _ = cache.icon("mpv");
+            for icon in cache.iter() {
+                assert!(
+                    icon.image_list().unwrap().iter().count()
+                        <= icon.image_list().unwrap().len() as usize
+                );
+            }
+            assert!(cache.directory_list.iter().count() <= 59);
+        }
+    }
+
+    #[test]
+    fn unterminated_name_is_skipped() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+        let bucket = icon_str_hash("mpv") % cache.hash.n_buckets.get();
+        let name_field = cache.hash.icon[bucket as usize].offset.get() as usize + 4;
+
+        // Move the name of `mpv` to the very end of the cache, without a nul terminator
+        let mut bytes = SAMPLE_INDEX_FILE.to_vec();
+        let name = bytes.len() as u32;
+        bytes.extend_from_slice(b"mpv");
+        bytes[name_field..name_field + 4].copy_from_slice(&name.to_be_bytes());
+
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+        assert!(cache.icon("mpv").is_none());
+        assert_eq!(cache.iter().count(), 562);
+        assert_eq!(cache.names().count(), 562);
+    }
+
+    #[test]
+    fn test_owned_bytes_cache() -> Result<(), Box<dyn Error>> {
+        let owned = OwnedBytesCache::from_vec(SAMPLE_INDEX_FILE.to_vec());
+
+        let cache = owned.icon_cache()?;
+        assert_eq!(cache.iter().count(), 563);
+        assert!(cache.icon("mpv").is_some());
+
+        let owned = OwnedBytesCache::from_vec(vec![0; 3]);
+        assert_eq!(owned.icon_cache().unwrap_err(), IconCacheError::Truncated);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_swap_byte_order() -> Result<(), Box<dyn Error>> {
+        let mut bytes = SAMPLE_INDEX_FILE.to_vec();
+        IconCache::swap_byte_order(&mut bytes)?;
+
+        assert!(IconCache::is_byte_swapped(&bytes));
+        assert_eq!(&bytes[..4], [1, 0, 0, 0]);
+        assert_eq!(
+            IconCache::new_from_bytes(&bytes).unwrap_err(),
+            IconCacheError::ByteSwapped
+        );
+
+        let owned = OwnedBytesCache::from_vec(bytes.clone());
+        assert_eq!(owned.bytes, SAMPLE_INDEX_FILE);
+        assert_eq!(owned.icon_cache()?.iter().count(), 563);
+
+        IconCache::swap_byte_order(&mut bytes)?;
+        assert!(bytes == SAMPLE_INDEX_FILE);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_swap_byte_order_meta_data() {
+        let original = synthetic_cache_with_meta_data();
+        let mut bytes = original.clone();
+
+        IconCache::swap_byte_order(&mut bytes).unwrap();
+        assert_ne!(bytes, original);
+
+        let owned = OwnedBytesCache::from_vec(bytes);
+        assert_eq!(owned.bytes, original);
+    }
+
+    #[test]
+    fn test_icon_by_hash() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        let by_hash = cache.icon_by_hash(icon_str_hash(b"mpv"), b"mpv").unwrap();
+        let by_name = cache.icon("mpv").unwrap();
+        assert_eq!(by_hash.name, by_name.name);
+        assert!(core::ptr::eq(
+            by_hash.image_list().unwrap().raw_list,
+            by_name.image_list().unwrap().raw_list
+        ));
+
+        // The name is still compared, even if the bucket matches
+        assert!(cache.icon_by_hash(icon_str_hash(b"mpv"), b"vpm").is_none());
+        // A wrong hash picks the wrong bucket
+        assert!(
+            cache
+                .icon_by_hash(icon_str_hash(b"mpv") + 1, b"mpv")
+                .is_none()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_icon_eq() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        let mpv = cache.icon("mpv").unwrap();
+        assert_eq!(mpv, cache.icon("mpv").unwrap());
+        assert_ne!(mpv, cache.icon("nvim").unwrap());
+
+        let images = mpv.image_list().unwrap().iter().collect::<Vec<_>>();
+        assert_eq!(images[0], mpv.image_list().unwrap().image(0).unwrap());
+        assert_ne!(images[0], images[1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_icon_ci() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        assert!(cache.icon("Mpv").is_none());
+        assert_eq!(cache.icon_ci("Mpv").unwrap().name, c"mpv");
+        assert_eq!(cache.icon_ci("mpv").unwrap().name, c"mpv");
+        assert!(cache.icon_ci("does-not-exist").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_icon_or() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        let fallback = ["also-does-not-exist", "nvim", "mpv"];
+        assert_eq!(cache.icon_or("mpv", &fallback).unwrap().name, c"mpv");
+        assert_eq!(
+            cache.icon_or("does-not-exist", &fallback).unwrap().name,
+            c"nvim"
+        );
+        assert!(cache.icon_or("does-not-exist", &[]).is_none());
+        assert!(
+            cache
+                .icon_or("does-not-exist", &["also-does-not-exist"])
+                .is_none()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_icons() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        let icons = cache.icons(&["mpv", "does-not-exist", "mpv"]);
+        assert_eq!(
+            icons
+                .iter()
+                .map(|icon| icon.map(|icon| icon.name))
+                .collect::<Vec<_>>(),
+            [Some(c"mpv"), None, Some(c"mpv")]
+        );
+        assert!(cache.icons::<&str>(&[]).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_contains() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        assert!(cache.contains("mpv"));
+        assert!(!cache.contains("does-not-exist"));
+
+        for name in cache.names() {
+            assert_eq!(
+                cache.contains(name.to_bytes()),
+                cache.icon(name.to_bytes()).is_some(),
+                "{name:?}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_coverage_of() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        let (found, missing) =
+            cache.coverage_of(&["mpv", "missing-a", "xapp-favorite-symbolic", "missing-b"]);
+        assert_eq!(found, 2);
+        assert_eq!(missing, [b"missing-a".as_slice(), b"missing-b"]);
+
+        let (found, missing) = cache.coverage_of::<&str>(&[]);
+        assert_eq!(found, 0);
+        assert!(missing.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_icon_iter() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        assert_eq!(cache.iter().count(), 563);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_into_iterator() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        let mut count = 0;
+        for icon in &cache {
+            assert!(!icon.name.is_empty());
+            count += 1;
+        }
+
+        assert_eq!(count, 563);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_names() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        assert_eq!(cache.names().count(), 563);
+
+        let names = cache.take_names(20);
+        assert_eq!(names.len(), 20);
+        assert!(
+            names
+                .into_iter()
+                .eq(cache.iter().take(20).map(|icon| icon.name))
+        );
+
+        assert_eq!(cache.take_names(1000).len(), 563);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_all_images_with_names() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        assert_eq!(cache.all_images_with_names().count(), 1377);
+
+        let mpv_dirs = cache
+            .all_images_with_names()
+            .filter(|(name, _)| *name == c"mpv")
+            .map(|(_, image)| image.directory)
+            .collect::<Vec<_>>();
+        let expected = cache.icon("mpv").unwrap().image_list().unwrap();
+        assert!(
+            mpv_dirs
+                .into_iter()
+                .eq(expected.iter().map(|image| image.directory))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lookup_footprint() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        // header and hash length, the bucket, and the icon and its name which share a line
+        assert_eq!(cache.lookup_footprint("mpv"), 3);
+        // a miss walks the entire chain of the bucket
+        assert_eq!(cache.lookup_footprint("nonexistent-icon"), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        assert_eq!(
+            cache.summary(),
+            CacheSummary {
+                version: (1, 0),
+                icon_count: 563,
+                directory_count: 59,
+                image_count: 1377,
+                // 230 of the 251 buckets are occupied
+                collision_rate: (563.0 - 230.0) / 563.0,
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_stats() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let stats = cache.hash_stats();
+
+        assert_eq!(stats.n_buckets, 251);
+        assert_eq!(stats.n_icons, 563);
+        assert_eq!(stats.n_icons, cache.iter().count());
+        assert_eq!(stats.empty_buckets, 21);
+        assert_eq!(stats.max_chain_length, 7);
+
+        let occupied_buckets = stats.n_buckets - stats.empty_buckets;
+        assert_eq!(
+            stats.mean_chain_length,
+            stats.n_icons as f64 / occupied_buckets as f64
+        );
+        assert!(stats.mean_chain_length >= cache.load_factor());
+        assert!(stats.mean_chain_length <= stats.max_chain_length as f64);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_image_count() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        assert_eq!(cache.image_count(), 1377);
+        assert_eq!(cache.image_count(), cache.summary().image_count);
+        assert_eq!(
+            cache.image_count(),
+            cache
+                .iter()
+                .map(|icon| icon.image_list().unwrap().len() as u64)
+                .sum::<u64>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_factor() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        assert_eq!(cache.load_factor(), 563.0 / 251.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_image_list_iter() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let icon = cache.icon("mpv").unwrap();
+
+        let count = icon.image_list().unwrap().iter().count();
+        assert_eq!(count, 5);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_directory_list_iter() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let dir_list = cache.directory_list;
+
+        assert_eq!(dir_list.len(), 59);
+
+        assert!(!cache.directory_list.is_empty());
+        assert_eq!(cache.directory_list.iter().count(), 59);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_resolve_all_paths() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let icon = cache.icon("mpv").unwrap();
+
+        let paths = icon.resolve_all_paths(Path::new("/usr/share/icons/hicolor"));
+
+        assert_eq!(
+            paths,
+            [
+                "/usr/share/icons/hicolor/scalable/apps/mpv.svg",
+                "/usr/share/icons/hicolor/64x64/apps/mpv.png",
+                "/usr/share/icons/hicolor/32x32/apps/mpv.png",
+                "/usr/share/icons/hicolor/16x16/apps/mpv.png",
+                "/usr/share/icons/hicolor/128x128/apps/mpv.png",
+            ]
+            .map(PathBuf::from)
+        );
+
+        let mut builder = write::CacheBuilder::new();
+        let both = raw::Flags::new(raw::Flags::HAS_SUFFIX_SVG | raw::Flags::HAS_SUFFIX_PNG);
+        builder.add_image(b"both", Path::new("48x48/apps"), both);
+        let bytes = builder.build();
+        let cache = IconCache::new_from_bytes(&bytes)?;
+
+        assert_eq!(
+            cache
+                .icon("both")
+                .unwrap()
+                .resolve_all_paths(Path::new("/theme")),
+            ["/theme/48x48/apps/both.svg", "/theme/48x48/apps/both.png"].map(PathBuf::from)
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_image_file_paths() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let icon = cache.icon("mpv").unwrap();
+        let image = icon.image_list().unwrap().image(0).unwrap();
+
+        let paths = image
+            .file_paths(Path::new("/usr/share/icons/hicolor"), icon.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [PathBuf::from(
+                "/usr/share/icons/hicolor/scalable/apps/mpv.svg"
+            )]
+        );
+
+        let image = Image {
+            icon_flags: raw::Flags::new(raw::Flags::HAS_SUFFIX_XPM | raw::Flags::HAS_SUFFIX_PNG),
+            ..image
+        };
+        let paths = image.file_paths(Path::new("/"), c"mpv").collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            ["/scalable/apps/mpv.png", "/scalable/apps/mpv.xpm"].map(PathBuf::from)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    // Without `std` there is no `directory`, so the struct updates below set every field.
+    #[cfg_attr(not(feature = "std"), allow(clippy::needless_update))]
+    fn test_flags_consistent() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+        assert!(
+            cache
+                .all_images_with_names()
+                .all(|(_, image)| image.flags_consistent())
+        );
+
+        let bytes = synthetic_cache_with_meta_data();
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+        let image = cache
+            .icon("test")
+            .unwrap()
+            .image_list()
+            .unwrap()
+            .image(0)
+            .unwrap();
+        assert!(image.flags_consistent());
+
+        // meta data without an `.icon` file
+        let image = Image {
+            icon_flags: raw::Flags::new(raw::Flags::HAS_SUFFIX_PNG),
+            ..image
+        };
+        assert!(!image.flags_consistent());
+
+        // no file at all
+        let image = Image {
+            icon_flags: raw::Flags::default(),
+            image_data: None,
+            ..image
+        };
+        assert!(!image.flags_consistent());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_ranked_images_for_size() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let icon = cache.icon("mpv").unwrap();
+
+        let ranked = icon
+            .ranked_images_for_size(32, 1)
+            .into_iter()
+            .map(|(image, score)| (image.directory.to_str().unwrap(), score))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ranked,
+            [
+                ("32x32/apps", 0),
+                ("scalable/apps", 1),
+                ("16x16/apps", 33),
+                ("64x64/apps", 64),
+                ("128x128/apps", 192),
+            ]
+        );
+
+        let best = icon.best_image_for_size(32, 2).unwrap();
+        assert_eq!(best.directory, Path::new("64x64/apps"));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_size_format_table() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let icon = cache.icon("mpv").unwrap();
+
+        let table = icon.size_format_table();
+        let svg = raw::Flags::new(raw::Flags::HAS_SUFFIX_SVG);
+        let png = raw::Flags::new(raw::Flags::HAS_SUFFIX_PNG);
+
+        assert_eq!(table.len(), 5);
+        assert_eq!(
+            table[0],
+            (
+                DirectoryInfo {
+                    size: None,
+                    scale: 1,
+                    context: Some(Path::new("apps"))
+                },
+                svg
+            )
+        );
+        assert_eq!(
+            table[1],
+            (
+                DirectoryInfo {
+                    size: Some(64),
+                    scale: 1,
+                    context: Some(Path::new("apps"))
+                },
+                png
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_directory_refs() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+        assert!(!cache.has_invalid_directory_refs());
+
+        // Point the second image of `mpv` at a directory that doesn't exist
+        let image_list = cache.icon("mpv").unwrap().image_list().unwrap().raw_list;
+        let offset = offset_of(image_list);
+        let mut bytes = SAMPLE_INDEX_FILE.to_vec();
+        bytes[offset + 4 + 8..][..2].copy_from_slice(&1000u16.to_be_bytes());
+
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+        assert!(cache.has_invalid_directory_refs());
+        assert_eq!(cache.invalid_directory_refs().collect::<Vec<_>>(), [c"mpv"]);
+        assert!(
+            cache
+                .icon("mpv")
+                .unwrap()
+                .image_list()
+                .unwrap()
+                .image(1)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_image_iter_len() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let image_list = cache.icon("mpv").unwrap().image_list().unwrap();
+
+        let mut iter = image_list.iter();
+        assert_eq!(iter.len(), 5);
+        iter.next();
+        assert_eq!(iter.len(), 4);
+
+        let mut images = Vec::with_capacity(image_list.iter().len());
+        images.extend(image_list.iter());
+        assert_eq!(images.len(), images.capacity());
+        assert_eq!(image_list.iter().next_back(), image_list.image(4));
+
+        // Images that fail to parse still count toward the length, but are skipped
+        let offset = offset_of(image_list.raw_list);
+        let mut bytes = SAMPLE_INDEX_FILE.to_vec();
+        bytes[offset + 4 + 8..][..2].copy_from_slice(&1000u16.to_be_bytes());
+
+        let cache = IconCache::new_from_bytes(&bytes)?;
+        let iter = cache.icon("mpv").unwrap().image_list().unwrap().iter();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.count(), 4);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_directory_iter_rev() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let directory_list = cache.directory_list;
+
+        assert_eq!(
+            directory_list.iter().next_back(),
+            directory_list.dir(directory_list.len() - 1)
+        );
+
+        let forward = directory_list.iter().collect::<Vec<_>>();
+        let mut backward = directory_list.iter().rev().collect::<Vec<_>>();
+        backward.reverse();
+        assert_eq!(forward, backward);
+
+        // Both ends meet in the middle without visiting a directory twice
+        let mut iter = directory_list.iter();
+        assert_eq!(iter.next(), directory_list.dir(0));
+        assert_eq!(
+            iter.next_back(),
+            directory_list.dir(directory_list.len() - 1)
+        );
+        assert_eq!(iter.count(), directory_list.len() as usize - 2);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_find_directory() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let directory_list = cache.directory_list;
+
+        let idx = directory_list.find(Path::new("scalable/apps")).unwrap();
+        assert_eq!(directory_list.dir(idx), Some(Path::new("scalable/apps")));
+
+        assert_eq!(directory_list.find("nonexistent/directory"), None);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_images_in_directory() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let image_list = cache.icon("mpv").unwrap().image_list().unwrap();
+
+        let scalable = image_list
+            .images_in_directory(Path::new("scalable/apps"))
+            .collect::<Vec<_>>();
+        assert_eq!(scalable.len(), 1);
+        assert_eq!(scalable[0].directory, Path::new("scalable/apps"));
+        assert!(scalable[0].icon_flags.has_suffix_svg());
+
+        let apps = image_list.images_in(|dir| dir.ends_with("apps")).count();
+        assert_eq!(apps, image_list.len() as usize);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_combined_flags() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        let flags = cache.icon("mpv").unwrap().combined_flags();
+        assert!(flags.has_suffix_svg());
+        assert!(flags.has_suffix_png());
+        assert!(!flags.has_suffix_xpm());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_to_owned_map() -> Result<(), Box<dyn Error>> {
+        let map = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?.to_owned_map();
+        assert_eq!(map.len(), 563);
+
+        let mpv = &map["mpv"];
+        assert_eq!(mpv.len(), 5);
+        assert_eq!(mpv[0].directory, Path::new("scalable/apps"));
+        assert!(mpv[0].icon_flags.has_suffix_svg());
+        assert_eq!(mpv[0].meta_data, None);
+
+        let bytes = synthetic_cache_with_meta_data();
+        let map = IconCache::new_from_bytes(&bytes)?.to_owned_map();
+        let meta_data = map["test"][0].meta_data.as_ref().unwrap();
+        assert!(meta_data.embedded_rect.is_some());
+        assert_eq!(meta_data.attach_points.len(), 2);
+        assert_eq!(meta_data.display_names.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_image_embedded_rect() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let image = cache
+            .icon("mpv")
+            .unwrap()
+            .image_list()
+            .unwrap()
+            .image(0)
+            .unwrap();
+        assert_eq!(image.embedded_rect(), None);
+
+        let bytes = synthetic_cache_with_meta_data();
+        let cache = IconCache::new_from_bytes(&bytes)?;
+        let image = cache
+            .icon("test")
+            .unwrap()
+            .image_list()
+            .unwrap()
+            .image(0)
+            .unwrap();
+        assert_eq!(
+            image.embedded_rect(),
+            Some(raw::EmbeddedRect {
+                x0: U16::new(1),
+                y0: U16::new(2),
+                x1: U16::new(30),
+                y1: U16::new(40),
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_try_from_bytes() -> Result<(), Box<dyn Error>> {
+        let cache: IconCache = SAMPLE_INDEX_FILE.try_into()?;
+        assert!(cache.contains("mpv"));
+
+        let truncated: Result<IconCache, _> = SAMPLE_INDEX_FILE[..4].try_into();
+        assert_eq!(truncated.unwrap_err(), IconCacheError::Truncated);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_all_images() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        assert_eq!(cache.all_images().count() as u64, cache.image_count());
+
+        let mpv = cache
+            .all_images()
+            .filter(|(name, _)| *name == c"mpv")
+            .map(|(_, image)| image)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            mpv,
+            cache
+                .icon("mpv")
+                .unwrap()
+                .image_list()
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_icon_with_probes() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+
+        // The first icon of the first non-empty bucket is found immediately
+        let first = cache
+            .hash
+            .icon
+            .iter()
+            .find(|offset| !offset.is_null())
+            .unwrap()
+            .at(SAMPLE_INDEX_FILE)?;
+        let name = first.name.str_at(SAMPLE_INDEX_FILE)?;
+
+        let (icon, probes) = cache.icon_with_probes(name.to_bytes());
+        assert_eq!(icon.unwrap().name, name);
+        assert_eq!(probes, 1);
+
+        // A missing icon walks its whole chain
+        let (icon, probes) = cache.icon_with_probes("nonexistent-icon");
+        let bucket = icon_str_hash("nonexistent-icon") % cache.hash.n_buckets.get();
+        let chain_len = cache
+            .icon_chain(bucket)
+            .map_or(0, |icon| icon.iter(SAMPLE_INDEX_FILE).count());
+        assert!(icon.is_none());
+        assert_eq!(probes as usize, chain_len);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_by_size() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let image_list = cache.icon("mpv").unwrap().image_list().unwrap();
+
+        let groups = image_list.by_size();
+        assert_eq!(
+            groups.values().map(Vec::len).sum::<usize>(),
+            image_list.len() as usize
+        );
+
+        let scalable = &groups[&None];
+        assert!(!scalable.is_empty());
+        assert!(
+            scalable
+                .iter()
+                .all(|image| image.directory.starts_with("scalable"))
+        );
+
+        for (size, images) in &groups {
+            assert!(
+                images
+                    .iter()
+                    .all(|image| DirectoryInfo::parse(image.directory).size == *size)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_sorted_by_size() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let image_list = cache.icon("mpv").unwrap().image_list().unwrap();
+
+        let images = image_list.sorted_by_size();
+        assert_eq!(images.len(), image_list.len() as usize);
+
+        let sizes = images
+            .iter()
+            .map(|image| DirectoryInfo::parse(image.directory).size)
+            .collect::<Vec<_>>();
+        let (scalable, sized) = sizes.split_last().unwrap();
+        assert_eq!(*scalable, None);
+        assert!(sized.is_sorted());
+        assert!(sized.iter().all(Option::is_some));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_non_utf8_icon_name() -> Result<(), Box<dyn Error>> {
+        let name = b"caf\xE9";
+
+        let mut builder = write::CacheBuilder::new();
+        builder
+            .add_image(
+                name,
+                "48x48/apps",
+                raw::Flags::new(raw::Flags::HAS_SUFFIX_PNG),
+            )
+            .add_image(
+                "other",
+                "48x48/apps",
+                raw::Flags::new(raw::Flags::HAS_SUFFIX_PNG),
+            );
+        let bytes = builder.build();
+        let cache = IconCache::new_from_bytes(&bytes)?;
+
+        let icon = cache.icon_bytes(name).unwrap();
+        assert_eq!(icon.name.to_bytes(), name);
+        assert!(icon.name.to_str().is_err());
+        assert!(cache.contains(name));
+
+        assert_eq!(cache.iter().count(), 2);
+        assert!(cache.names().any(|icon_name| icon_name.to_bytes() == name));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bucket_len() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        assert_eq!(cache.bucket_count(), 251);
+
+        let lens = (0..cache.bucket_count())
+            .map(|bucket| cache.bucket_len(bucket))
+            .collect::<Vec<_>>();
+        assert_eq!(lens.iter().sum::<u32>(), 563);
+        assert_eq!(lens.iter().filter(|&&len| len == 0).count(), 21);
+        assert_eq!(lens.iter().max(), Some(&7));
+
+        assert_eq!(cache.bucket_len(cache.bucket_count()), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_buckets() -> Result<(), Box<dyn Error>> {
+        let mut s = Synth::default();
+        // header
+        s.u16(1).u16(0).u32(12).u32(16);
+        // hash without any buckets
+        s.u32(0);
+        // empty directory list
+        s.u32(0);
+
+        let cache = IconCache::new_from_bytes(&s.0)?;
+        assert!(cache.icon("mpv").is_none());
+        assert!(!cache.contains("mpv"));
+        assert_eq!(cache.icon_with_probes("mpv"), (None, 0));
+        assert_eq!(cache.iter().count(), 0);
+        assert_eq!(cache.lookup_footprint("mpv"), 1);
+        assert_eq!(cache.bucket_len(0), 0);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_image_with_format() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let mpv = cache.icon("mpv").unwrap();
+
+        let svg = mpv
+            .image_with_format(raw::Flags::new(raw::Flags::HAS_SUFFIX_SVG))
+            .unwrap();
+        assert!(svg.icon_flags.has_suffix_svg());
+        assert_eq!(svg.directory, Path::new("scalable/apps"));
+
+        let png = mpv
+            .image_with_format(raw::Flags::new(raw::Flags::HAS_SUFFIX_PNG))
+            .unwrap();
+        assert!(png.icon_flags.has_suffix_png());
+
+        assert!(
+            mpv.image_with_format(raw::Flags::new(raw::Flags::HAS_SUFFIX_XPM))
+                .is_none()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_chain_cycle() -> Result<(), Box<dyn Error>> {
+        let mut s = Synth::default();
+
+        // header
+        s.u16(1).u16(0).u32(12).u32(0);
+        // hash with one bucket
+        s.u32(1).u32(20);
+        // two icons, the second chaining back to the first
+        s.u32(32).u32(44).u32(56);
+        s.u32(20).u32(48).u32(56);
+        s.str(b"one").str(b"two");
+        // empty image list
+        s.u32(0);
+
+        let dir_list = s.pos();
+        s.u32(0);
+        s.set_u32(8, dir_list);
+
+        let cache = IconCache::new_from_bytes(&s.0)?;
+        assert_eq!(cache.names().collect::<Vec<_>>(), [c"one", c"two"]);
+        assert_eq!(cache.iter().count(), 2);
+        assert!(cache.icon("two").is_some());
+        assert!(!cache.contains("three"));
+        assert_eq!(cache.icon_with_probes("three"), (None, 2));
+        assert_eq!(cache.bucket_len(0), 2);
+        assert_eq!(cache.hash_stats().max_chain_length, 2);
+
+        // An icon chaining to itself
+        s.set_u32(32, 32);
+        let cache = IconCache::new_from_bytes(&s.0)?;
+        assert_eq!(cache.names().collect::<Vec<_>>(), [c"one", c"two"]);
+        assert!(!cache.contains("three"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+
+        let debug = format!("{cache:?}");
+        assert_eq!(
+            debug,
+            "IconCache { version: (1, 0), n_buckets: 251, n_icons: 563, n_directories: 59 }"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_used_directories() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+        let all = cache.directory_list.iter().collect::<Vec<_>>();
+
+        let used = cache.used_directories().collect::<Vec<_>>();
+        assert!(!used.is_empty());
+        assert!(used.iter().all(|directory| all.contains(directory)));
+        assert!(used.is_sorted_by_key(|directory| cache.directory_list.find(directory)));
+
+        let expected = cache
+            .all_images()
+            .map(|(_, image)| image.directory)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(used.len(), expected.len());
+
+        // A directory no image refers to
+        let mut builder = write::CacheBuilder::new();
+        builder.add_directory("unused");
+        builder.add_image("mpv", "scalable/apps", raw::Flags::default());
+        let bytes = builder.build();
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+        assert_eq!(
+            cache.used_directories().collect::<Vec<_>>(),
+            [Path::new("scalable/apps")]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_icons_in_directory() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+
+        let names = cache
+            .icons_in_directory(Path::new("scalable/apps"))
+            .collect::<Vec<_>>();
+        assert!(names.contains(&c"mpv"));
+        assert!(names.len() > 1);
+        for name in &names {
+            let icon = cache.icon(name.to_bytes()).unwrap();
+            assert!(
+                icon.image_list()
+                    .unwrap()
+                    .images_in_directory(Path::new("scalable/apps"))
+                    .next()
+                    .is_some()
+            );
+        }
+
+        assert_eq!(
+            cache
+                .icons_in_directory(Path::new("no/such/directory"))
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+        assert_eq!(cache.validate(), Ok(()));
+
+        // Point the second image of `mpv` at a directory that doesn't exist
+        let image_list = cache.icon("mpv").unwrap().image_list().unwrap().raw_list;
+        let offset = offset_of(image_list);
+        let mut bytes = SAMPLE_INDEX_FILE.to_vec();
+        bytes[offset + 4 + 8..][..2].copy_from_slice(&1000u16.to_be_bytes());
+
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+        let error = cache.validate().unwrap_err();
+        assert_eq!(
+            error.problem,
+            validate::Problem::InvalidDirectoryIndex {
+                offset: offset + 4 + 8,
+                directory_index: 1000,
+            }
+        );
+        assert_eq!(error.icon_name, Some(c"mpv"));
+        assert_eq!(
+            error.bucket,
+            Some(icon_str_hash("mpv") % cache.hash.n_buckets.get())
+        );
+
+        let mut bytes = synthetic_cache_with_meta_data();
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+        assert_eq!(cache.validate(), Ok(()));
+
+        // Point the name of the icon past the end of the cache
+        let len = bytes.len();
+        bytes[24..28].copy_from_slice(&(len as u32).to_be_bytes());
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+        let error = cache.validate().unwrap_err();
+        assert_eq!(
+            error.problem,
+            validate::Problem::OutOfBounds {
+                kind: validate::RegionKind::IconName,
+                offset: len,
+            }
+        );
+        assert_eq!(error.icon_name, None);
+    }
+
+    #[test]
+    fn test_validate_all() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+        assert_eq!(cache.validate_all(), []);
+
+        let mut bytes = SAMPLE_INDEX_FILE.to_vec();
+        let len = bytes.len();
+
+        // Point the first image of `mpv` at a directory that doesn't exist
+        let mpv_images = offset_of(cache.icon("mpv").unwrap().image_list().unwrap().raw_list);
+        bytes[mpv_images + 4..][..2].copy_from_slice(&1000u16.to_be_bytes());
+
+        // Point the name of `nvim` past the end of the cache
+        let (_, nvim) = cache.find_raw_icon(icon_str_hash("nvim"), b"nvim").unwrap();
+        let nvim_name = offset_of(&nvim.name);
+        bytes[nvim_name..][..4].copy_from_slice(&(len as u32).to_be_bytes());
+
+        // Point the first directory past the end of the cache
+        let directory = offset_of(&cache.directory_list.raw_list.directory[0]);
+        bytes[directory..][..4].copy_from_slice(&(len as u32 + 4).to_be_bytes());
+
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+        let errors = cache.validate_all();
+        assert_eq!(errors.len(), 3, "{errors:?}");
+
+        assert_eq!(errors[0].bucket, None);
+        assert_eq!(
+            errors[0].problem,
+            validate::Problem::OutOfBounds {
+                kind: validate::RegionKind::Directory,
+                offset: len + 4,
+            }
+        );
+
+        assert!(errors.iter().any(|error| error.icon_name == Some(c"mpv")
+            && error.problem
+                == validate::Problem::InvalidDirectoryIndex {
+                    offset: mpv_images + 4,
+                    directory_index: 1000,
+                }));
+        assert!(errors.iter().any(|error| error.icon_name.is_none()
+            && error.bucket == Some(icon_str_hash("nvim") % cache.hash.n_buckets.get())
+            && error.problem
+                == validate::Problem::OutOfBounds {
+                    kind: validate::RegionKind::IconName,
+                    offset: len,
+                }));
+
+        // `validate` stops at the first of them
+        assert_eq!(cache.validate(), Err(errors[0]));
+    }
+
+    #[test]
+    fn test_validate_no_overlaps() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+        assert_eq!(cache.validate_no_overlaps(), Ok(()));
+
+        let mut bytes = synthetic_cache_with_meta_data();
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+        assert_eq!(cache.validate_no_overlaps(), Ok(()));
+
+        // Point the name of the icon into the middle of the hash table
+        bytes[24..28].copy_from_slice(&13u32.to_be_bytes());
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+
+        let overlaps = cache.validate_no_overlaps().unwrap_err();
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(overlaps[0].first.kind, validate::RegionKind::Hash);
+        assert_eq!(
+            overlaps[0].second,
+            validate::Region {
+                kind: validate::RegionKind::IconName,
+                offset: 13,
+                len: 1,
+            }
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_meta_data() {
+        let bytes = synthetic_cache_with_meta_data();
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+
+        let image = cache
+            .icon("test")
+            .unwrap()
+            .image_list()
+            .unwrap()
+            .image(0)
+            .unwrap();
+        assert_eq!(image.directory.to_str(), Some("48x48/apps"));
+
+        let meta_data = image.image_data.unwrap().image_meta_data.unwrap();
+
+        let rect = meta_data.embedded_rect().unwrap();
+        assert_eq!(
+            rect,
+            &raw::EmbeddedRect {
+                x0: U16::new(1),
+                y0: U16::new(2),
+                x1: U16::new(30),
+                y1: U16::new(40),
+            }
+        );
+
+        let attach_points = meta_data.attach_points().unwrap();
+        assert_eq!(attach_points.len(), 2);
+        assert_eq!(attach_points.point(1), Some((7, 8)));
+        assert_eq!(attach_points.point(2), None);
+        assert_eq!(attach_points.iter().collect::<Vec<_>>(), [(5, 6), (7, 8)]);
+
+        let display_names = meta_data.display_names().unwrap();
+        assert_eq!(display_names.len(), 2);
+        assert_eq!(
+            display_names.iter().collect::<Vec<_>>(),
+            [(c"de", c"Prüfung"), (c"", c"Test")]
+        );
+        assert_eq!(display_names.lookup("de"), Some(c"Prüfung"));
+        assert_eq!(display_names.lookup("fr"), Some(c"Test"));
+
+        // None of the images in the sample cache carry any image data
+        let sample = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+        assert!(sample.iter().all(|icon| {
+            icon.image_list()
+                .unwrap()
+                .iter()
+                .all(|image| image.image_data.is_none())
+        }));
+    }
+
+    #[test]
+    fn test_pixel_bytes() {
+        let bytes = synthetic_cache_with_pixel_data(b"pixels");
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+
+        let image = cache
+            .icon("test")
+            .unwrap()
+            .image_list()
+            .unwrap()
+            .image(0)
+            .unwrap();
+        assert_eq!(
+            image.image_data.unwrap().pixel_bytes(),
+            Some(&b"pixels"[..])
+        );
+
+        let bytes = synthetic_cache_with_meta_data();
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+
+        let image = cache
+            .icon("test")
+            .unwrap()
+            .image_list()
+            .unwrap()
+            .image(0)
+            .unwrap();
+        assert_eq!(image.image_data.unwrap().pixel_bytes(), None);
+    }
+
+    #[test]
+    fn test_pixel_data_len_and_type() {
+        let bytes = synthetic_cache_with_pixel_data(b"pixels");
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+
+        let image = cache
+            .icon("test")
+            .unwrap()
+            .image_list()
+            .unwrap()
+            .image(0)
+            .unwrap();
+        let image_data = image.image_data.unwrap();
+        assert_eq!(image_data.pixel_data_len(), Some(6));
+        assert_eq!(image_data.pixel_data_type(), Some(0));
+        assert_eq!(image_data.image_pixel_data_length, 6);
+        assert_eq!(image_data.image_pixel_data_type, 0);
+
+        let bytes = synthetic_cache_with_meta_data();
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+
+        let image = cache
+            .icon("test")
+            .unwrap()
+            .image_list()
+            .unwrap()
+            .image(0)
+            .unwrap();
+        let image_data = image.image_data.unwrap();
+        assert_eq!(image_data.pixel_data_len(), None);
+        assert_eq!(image_data.pixel_data_type(), None);
+        assert_eq!(image_data.image_pixel_data_length, 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_non_utf8_directory() {
+        let directory = Path::new(OsStr::from_bytes(b"48x48/\xFFapps"));
+
+        let mut builder = write::CacheBuilder::new();
+        builder.add_image(
+            "test",
+            directory,
+            raw::Flags::new(raw::Flags::HAS_SUFFIX_PNG),
+        );
+        let bytes = builder.build();
+
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+        assert_eq!(cache.directory_list.iter().collect::<Vec<_>>(), [directory]);
+
+        let image = cache
+            .icon("test")
+            .unwrap()
+            .image_list()
+            .unwrap()
+            .image(0)
+            .unwrap();
+        assert_eq!(image.directory, directory);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_directory_info() -> Result<(), Box<dyn Error>> {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE)?;
+        let dir_list = cache.directory_list;
+
+        let infos = (0..dir_list.len())
+            .map(|idx| dir_list.info(idx))
+            .collect::<Option<Vec<_>>>()
+            .unwrap();
+        assert_eq!(infos.len(), 59);
+        assert!(dir_list.info(59).is_none());
+
+        fn info(size: Option<u32>, context: Option<&str>) -> DirectoryInfo<'_> {
+            DirectoryInfo {
+                size,
+                scale: 1,
+                context: context.map(Path::new),
+            }
+        }
+
+        assert_eq!(infos[0], info(Some(1024), Some("apps")));
+        assert_eq!(infos[6], info(Some(16), None));
+        assert_eq!(infos[51], info(None, Some("apps")));
+        assert_eq!(infos[58], info(None, Some("apps")));
+
+        assert_eq!(
+            DirectoryInfo::parse(Path::new("16x16@2x/mimetypes")),
+            DirectoryInfo {
+                size: Some(16),
+                scale: 2,
+                context: Some(Path::new("mimetypes"))
+            }
+        );
 
         Ok(())
     }
@@ -356,6 +3358,31 @@ mod tests {
         assert_eq!(icon_str_hash("hello world"), 1794106052);
     }
 
+    #[test]
+    fn icon_str_hash_matches_gtk() {
+        // Computed with GTK's `icon_name_hash`
+        let expected: [(&[u8], u32); 10] = [
+            (b"a", 97),
+            (b"mpv", 108339),
+            (b"nvim", 3393772),
+            (b"preferences-other-symbolic", 900428356),
+            (b"application-x-executable", 213956124),
+            (b"image-missing", 3488121588),
+            ("é".as_bytes(), 4294965318),
+            ("café".as_bytes(), 94414350),
+            ("日本".as_bytes(), 3450900514),
+            (b"\xff", 4294967295),
+        ];
+
+        for (name, hash) in expected {
+            assert_eq!(icon_str_hash(name), hash, "{name:?}");
+        }
+
+        // Like GTK, hashing stops at a nul byte
+        assert_eq!(icon_str_hash(b"mpv\0junk"), icon_str_hash("mpv"));
+        assert_eq!(icon_str_hash(b"\0mpv"), 0);
+    }
+
     #[test]
     fn icon_str_hash_sym() {
         assert_eq!(icon_str_hash("preferences-other-symbolic") % 251, 243);
@@ -365,4 +3392,14 @@ mod tests {
     fn image_size_correct() {
         assert_eq!(size_of::<raw::Image>(), 8);
     }
+
+    #[test]
+    fn unaligned_bytes_parse() {
+        let mut buf = vec![0u8];
+        buf.extend_from_slice(SAMPLE_INDEX_FILE);
+        let bytes = &buf[1..];
+
+        let cache = IconCache::new_from_bytes(bytes).unwrap();
+        assert!(cache.icon("mpv").is_some());
+    }
 }
@@ -0,0 +1,52 @@
+//! Parallel iteration over icon caches, enabled by the `rayon` feature.
+
+use crate::{Icon, IconCache};
+use rayon::prelude::*;
+
+impl<'a> IconCache<'a> {
+    /// Returns a parallel iterator over all icons in the cache.
+    ///
+    /// The buckets of the hash table are distributed across threads; every bucket's chain of
+    /// icons is walked sequentially. Like [iter](Self::iter), icons that cannot be parsed are
+    /// skipped.
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = Icon<'a>> {
+        let cache = *self;
+
+        (0..self.hash.n_buckets.get())
+            .into_par_iter()
+            .filter_map(move |bucket| cache.icon_chain(bucket))
+            .flat_map_iter(move |chain| {
+                chain
+                    .iter(cache.bytes)
+                    .filter_map(move |icon| cache.icon_from_raw(icon))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::IconCache;
+    use rayon::prelude::*;
+
+    static SAMPLE_INDEX_FILE: &[u8] = include_bytes!("../assets/icon-theme.cache");
+
+    #[test]
+    fn par_iter_count() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+
+        assert_eq!(cache.par_iter().count(), 563);
+        assert_eq!(cache.par_iter().count(), cache.iter().count());
+    }
+
+    #[test]
+    fn par_iter_names() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+
+        let mut parallel = cache.par_iter().map(|icon| icon.name).collect::<Vec<_>>();
+        let mut serial = cache.names().collect::<Vec<_>>();
+        parallel.sort();
+        serial.sort();
+
+        assert_eq!(parallel, serial);
+    }
+}
@@ -0,0 +1,191 @@
+//! Decoding of the `GdkPixdata` pixel data embedded in caches.
+
+use crate::ImageData;
+use image::RgbaImage;
+
+/// `GdkP`
+const MAGIC: u32 = 0x47646B50;
+const HEADER_LENGTH: usize = 24;
+
+const COLOR_TYPE_RGB: u32 = 0x01;
+const COLOR_TYPE_RGBA: u32 = 0x02;
+const COLOR_TYPE_MASK: u32 = 0xFF;
+const SAMPLE_WIDTH_8: u32 = 0x01 << 16;
+const SAMPLE_WIDTH_MASK: u32 = 0x0F << 16;
+const ENCODING_RAW: u32 = 0x01 << 24;
+const ENCODING_RLE: u32 = 0x02 << 24;
+const ENCODING_MASK: u32 = 0x0F << 24;
+
+/// Pixel data type of `GdkPixdata`, the only type `gtk-update-icon-cache` writes.
+const PIXEL_DATA_TYPE_PIXDATA: u32 = 0;
+
+impl ImageData<'_> {
+    /// Decode the pixel data embedded in the cache for this image.
+    ///
+    /// Returns `None` if the image has no pixel data, or if it is not a valid `GdkPixdata` with
+    /// 8-bit RGB or RGBA samples, stored either raw or run-length encoded.
+    pub fn to_rgba(&self) -> Option<RgbaImage> {
+        let (pixel_data_type, pixel_data) = self.pixel_data()?;
+        if pixel_data_type != PIXEL_DATA_TYPE_PIXDATA {
+            return None;
+        }
+
+        decode(pixel_data)
+    }
+}
+
+fn decode(pixdata: &[u8]) -> Option<RgbaImage> {
+    let header = pixdata.get(..HEADER_LENGTH)?;
+    let field = |idx: usize| u32::from_be_bytes(header[4 * idx..4 * idx + 4].try_into().unwrap());
+
+    let [magic, _length, pixdata_type, rowstride, width, height] = [0, 1, 2, 3, 4, 5].map(field);
+    if magic != MAGIC || pixdata_type & SAMPLE_WIDTH_MASK != SAMPLE_WIDTH_8 {
+        return None;
+    }
+
+    let bpp = match pixdata_type & COLOR_TYPE_MASK {
+        COLOR_TYPE_RGB => 3,
+        COLOR_TYPE_RGBA => 4,
+        _ => return None,
+    };
+
+    let pixels = &pixdata[HEADER_LENGTH..];
+    let n_pixels = (width as usize).checked_mul(height as usize)?;
+    // The dimensions come from the header and may claim far more pixels than the payload holds:
+    // only reserve what the payload can account for, as raw pixels.
+    let capacity = n_pixels.checked_mul(4)?.min(pixels.len() / bpp * 4);
+    let mut rgba = Vec::with_capacity(capacity);
+    let mut push = |pixel: &[u8]| {
+        rgba.extend_from_slice(&pixel[..3]);
+        rgba.push(if bpp == 4 { pixel[3] } else { 0xFF });
+    };
+
+    match pixdata_type & ENCODING_MASK {
+        ENCODING_RAW => {
+            let row_len = width as usize * bpp;
+            if (rowstride as usize) < row_len {
+                return None;
+            }
+
+            for y in 0..height as usize {
+                let start = y * rowstride as usize;
+                let row = pixels.get(start..start + row_len)?;
+                row.chunks_exact(bpp).for_each(&mut push);
+            }
+        }
+        ENCODING_RLE => {
+            // Rows aren't padded to the rowstride: the pixels are encoded one after another in
+            // chunks, each starting with a byte holding the amount of pixels in the chunk. If its
+            // high bit is set, a single pixel follows which is repeated that many times. Otherwise,
+            // that many pixels follow.
+            let mut pixels = pixels;
+            let mut decoded = 0;
+
+            while decoded < n_pixels {
+                let (&chunk, rest) = pixels.split_first()?;
+                let count = (chunk & 0x7F) as usize;
+                if decoded + count > n_pixels {
+                    return None;
+                }
+
+                if chunk & 0x80 != 0 {
+                    let pixel = rest.get(..bpp)?;
+                    (0..count).for_each(|_| push(pixel));
+                    pixels = &rest[bpp..];
+                } else {
+                    let run = rest.get(..count * bpp)?;
+                    run.chunks_exact(bpp).for_each(&mut push);
+                    pixels = &rest[count * bpp..];
+                }
+
+                decoded += count;
+            }
+        }
+        _ => return None,
+    }
+
+    RgbaImage::from_raw(width, height, rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::IconCache;
+    use crate::tests::synthetic_cache_with_pixel_data;
+
+    fn pixdata(
+        pixdata_type: u32,
+        rowstride: u32,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) -> Vec<u8> {
+        let mut bytes = vec![];
+        let length = (super::HEADER_LENGTH + pixels.len()) as u32;
+        for field in [super::MAGIC, length, pixdata_type, rowstride, width, height] {
+            bytes.extend_from_slice(&field.to_be_bytes());
+        }
+        bytes.extend_from_slice(pixels);
+        bytes
+    }
+
+    fn decode_from_cache(pixdata: &[u8]) -> Option<image::RgbaImage> {
+        let bytes = synthetic_cache_with_pixel_data(pixdata);
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+        let image = cache.icon("test")?.image_list()?.image(0)?;
+
+        image.image_data?.to_rgba()
+    }
+
+    #[test]
+    fn decode_raw_rgba() {
+        let pixels = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+        let pixdata = pixdata(0x01010002, 8, 2, 2, &pixels);
+
+        let image = decode_from_cache(&pixdata).unwrap();
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(image.as_raw(), &pixels);
+    }
+
+    #[test]
+    fn decode_raw_rgb_with_padding() {
+        // Two rows of two RGB pixels, each row padded to 8 bytes
+        let pixels = [1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 10, 11, 12, 0, 0];
+        let pixdata = pixdata(0x01010001, 8, 2, 2, &pixels);
+
+        let image = decode_from_cache(&pixdata).unwrap();
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(image.get_pixel(0, 0).0, [1, 2, 3, 0xFF]);
+        assert_eq!(image.get_pixel(1, 1).0, [10, 11, 12, 0xFF]);
+    }
+
+    #[test]
+    fn decode_rle() {
+        // A run of 3 identical pixels, followed by a single literal pixel
+        let pixels = [0x83, 1, 2, 3, 4, 0x01, 5, 6, 7, 8];
+        let pixdata = pixdata(0x02010002, 8, 2, 2, &pixels);
+
+        let image = decode_from_cache(&pixdata).unwrap();
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(
+            image.as_raw(),
+            &[1, 2, 3, 4, 1, 2, 3, 4, 1, 2, 3, 4, 5, 6, 7, 8]
+        );
+    }
+
+    #[test]
+    fn decode_invalid() {
+        // Too few pixels
+        assert!(decode_from_cache(&pixdata(0x01010002, 8, 2, 2, &[0; 12])).is_none());
+        // RLE run overflowing the image
+        assert!(decode_from_cache(&pixdata(0x02010002, 8, 2, 2, &[0x85, 1, 2, 3, 4])).is_none());
+        // Huge dimensions with hardly any pixels
+        let huge = u16::MAX as u32;
+        assert!(decode_from_cache(&pixdata(0x01010002, huge * 4, huge, huge, &[0; 8])).is_none());
+        let rle = pixdata(0x02010002, huge * 4, huge, huge, &[0x81, 0, 0, 0, 0]);
+        assert!(decode_from_cache(&rle).is_none());
+        // 16-bit samples
+        assert!(decode_from_cache(&pixdata(0x01020002, 16, 2, 2, &[0; 32])).is_none());
+        // Not a GdkPixdata
+        assert!(decode_from_cache(b"not pixdata").is_none());
+    }
+}
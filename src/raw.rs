@@ -1,7 +1,17 @@
 //! FFI-compatible 'raw' structs matching the exact layout of the icon cache file.
 
-use std::ffi::{CStr, FromBytesUntilNulError};
-use std::marker::PhantomData;
+use alloc::collections::BTreeSet;
+use core::error::Error;
+use core::ffi::{CStr, FromBytesUntilNulError};
+use core::fmt::{Display, Formatter};
+use core::marker::PhantomData;
+use core::ops::{BitAnd, BitOr, BitOrAssign};
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::ffi::OsStr;
+#[cfg(feature = "std")]
+use std::os::unix::ffi::OsStrExt;
+#[cfg(feature = "std")]
 use std::path::Path;
 use zerocopy::{
     byteorder::network_endian::{U16, U32},
@@ -9,7 +19,7 @@ use zerocopy::{
 };
 
 #[repr(C)]
-#[derive(derive_more::Debug, FromBytes, KnownLayout, Immutable, Eq, PartialEq)]
+#[derive(derive_more::Debug, FromBytes, KnownLayout, Immutable, IntoBytes, Eq, PartialEq)]
 pub struct Offset<V, T: ?Sized> {
     pub offset: V,
     #[debug(skip)]
@@ -21,9 +31,14 @@ where
     V: Into<u32> + Copy,
     T: TryFromBytes + KnownLayout + Immutable + ?Sized,
 {
+    /// Interpret the bytes at this offset as a `T`.
+    ///
+    /// An offset past the end of `bytes` is treated as pointing at an empty slice, failing with a
+    /// size error for any non-empty `T`.
     pub fn at<'a>(&self, bytes: &'a [u8]) -> Result<&'a T, TryCastError<&'a [u8], T>> {
         let offset = self.offset.into() as usize;
-        T::try_ref_from_prefix(&bytes[offset..]).map(|(t, _)| t)
+        let bytes = bytes.get(offset..).unwrap_or_default();
+        T::try_ref_from_prefix(bytes).map(|(t, _)| t)
     }
 }
 
@@ -31,21 +46,24 @@ impl<V> Offset<V, CStr>
 where
     V: Into<u32> + Copy,
 {
+    /// Read the nul-terminated string at this offset.
+    ///
+    /// Returns an error if no nul byte is found before the end of `bytes`, including when the
+    /// offset is past the end of `bytes`.
     pub fn str_at<'a>(&self, bytes: &'a [u8]) -> Result<&'a CStr, FromBytesUntilNulError> {
         let offset = self.offset.into() as usize;
-        CStr::from_bytes_until_nul(&bytes[offset..])
+        let bytes = bytes.get(offset..).unwrap_or_default();
+        CStr::from_bytes_until_nul(bytes)
     }
-}
 
-impl<V> Offset<V, Path>
-where
-    V: Into<u32> + Copy,
-{
+    /// Read the nul-terminated path at this offset.
+    ///
+    /// Returns `None` if the offset is past the end of `bytes`, or no nul byte is found. Paths
+    /// don't need to be valid UTF-8.
+    #[cfg(feature = "std")]
     pub fn path_at<'a>(&self, bytes: &'a [u8]) -> Option<&'a Path> {
-        let offset = self.offset.into() as usize;
-        let cstr = CStr::from_bytes_until_nul(&bytes[offset..]).ok()?;
-        let str = cstr.to_str().ok()?;
-        Some(Path::new(str))
+        let cstr = self.str_at(bytes).ok()?;
+        Some(Path::new(OsStr::from_bytes(cstr.to_bytes())))
     }
 }
 
@@ -85,8 +103,68 @@ where
     }
 }
 
+/// A structure made up of a count followed by that many elements, like [DirectoryList] or
+/// [ImageList].
+///
+/// The trailing slice of such a structure doesn't necessarily hold as many elements as the count
+/// claims: parsed with [Offset::at], it extends as far as `bytes` allows. Use the provided methods
+/// rather than indexing the slice, which take both into account.
+pub trait LengthPrefixed {
+    type Element;
+
+    /// Returns the amount of elements the structure claims to hold.
+    fn count(&self) -> u32;
+
+    /// Returns every element that was parsed, which may be more or fewer than [count](Self::count).
+    fn parsed_elements(&self) -> &[Self::Element];
+
+    /// Returns the elements of the structure: the first [count](Self::count) elements, or fewer
+    /// if the structure is truncated.
+    fn elements(&self) -> &[Self::Element] {
+        let elements = self.parsed_elements();
+        &elements[..elements.len().min(self.count() as usize)]
+    }
+
+    /// Access an element by its index.
+    ///
+    /// Returns `None` if the index is not smaller than the count, or the element was not parsed.
+    fn get(&self, idx: u32) -> Option<&Self::Element> {
+        if idx >= self.count() {
+            return None;
+        }
+
+        self.parsed_elements().get(idx as usize)
+    }
+}
+
+macro_rules! impl_length_prefixed {
+    ($($ty:ty => $count:ident, $elements:ident: $element:ty;)*) => {
+        $(
+            impl LengthPrefixed for $ty {
+                type Element = $element;
+
+                fn count(&self) -> u32 {
+                    self.$count.get()
+                }
+
+                fn parsed_elements(&self) -> &[$element] {
+                    &self.$elements
+                }
+            }
+        )*
+    };
+}
+
+impl_length_prefixed! {
+    DirectoryList => n_directories, directory: Offset<U32, CStr>;
+    Hash => n_buckets, icon: Offset<U32, Icon>;
+    ImageList => n_images, images: Image;
+    AttachPointList => n_attach_points, attach_points: AttachPoint;
+    DisplayNameList => n_display_names, display_name: DisplayName;
+}
+
 #[repr(C)]
-#[derive(Debug, FromBytes, KnownLayout, Immutable, Eq, PartialEq)]
+#[derive(Debug, FromBytes, KnownLayout, Immutable, IntoBytes, Eq, PartialEq)]
 pub struct Header {
     pub major_version: U16,
     pub minor_version: U16,
@@ -98,7 +176,7 @@ pub struct Header {
 #[derive(Debug, FromBytes, KnownLayout, Immutable, Eq, PartialEq)]
 pub struct DirectoryList {
     pub n_directories: U32,
-    pub directory: [Offset<U32, Path>],
+    pub directory: [Offset<U32, CStr>],
 }
 
 #[repr(C)]
@@ -109,7 +187,7 @@ pub struct Hash {
 }
 
 #[repr(C)]
-#[derive(Debug, FromBytes, KnownLayout, Immutable, Eq, PartialEq)]
+#[derive(Debug, FromBytes, KnownLayout, Immutable, IntoBytes, Eq, PartialEq)]
 pub struct Icon {
     pub chain: Offset<U32, Icon>,
     pub name: Offset<U32, CStr>,
@@ -117,26 +195,89 @@ pub struct Icon {
 }
 
 impl Icon {
-    pub(crate) fn iter<'a>(&'a self, bytes: &'a [u8]) -> impl Iterator<Item = &'a Icon> {
-        let mut icon = Some(self);
+    /// Returns an iterator over the chain of icons starting at this icon.
+    ///
+    /// If the chain loops back onto itself, iteration stops before the first icon that would be
+    /// visited twice.
+    pub(crate) fn iter<'a>(&'a self, bytes: &'a [u8]) -> IconChain<'a> {
+        IconChain {
+            bytes,
+            head: self,
+            next: Some(self),
+            last: address(self),
+            visited: None,
+        }
+    }
+}
 
-        std::iter::from_fn(move || {
-            let result = icon;
+/// Iterator over a chain of icons, see [Icon::iter].
+#[derive(Debug, Clone)]
+pub(crate) struct IconChain<'a> {
+    bytes: &'a [u8],
+    head: &'a Icon,
+    next: Option<&'a Icon>,
+    /// Address of the furthest icon visited so far
+    last: usize,
+    /// Addresses of the visited icons. `gtk-update-icon-cache` writes every chain front to back,
+    /// so a chain that only moves forward can't loop: visited icons are only tracked once it
+    /// moves backward.
+    visited: Option<BTreeSet<usize>>,
+}
 
-            if let Some(result) = result {
-                if result.chain.is_null() {
-                    icon = None; // Next `result` will be None
-                    return Some(result);
+impl<'a> IconChain<'a> {
+    /// Returns `true` if `icon` hasn't been visited before, marking it as visited.
+    fn visit(&mut self, icon: &'a Icon) -> bool {
+        let icon_address = address(icon);
+
+        let visited = match &mut self.visited {
+            Some(visited) => visited,
+            None if icon_address > self.last => {
+                self.last = icon_address;
+                return true;
+            }
+            None => {
+                // Every icon up to and including `last` has been visited, in order
+                let mut visited = BTreeSet::new();
+                let mut icon = Some(self.head);
+                while let Some(visited_icon) = icon {
+                    visited.insert(address(visited_icon));
+                    if address(visited_icon) >= self.last {
+                        break;
+                    }
+
+                    icon = visited_icon.chain.at(self.bytes).ok();
                 }
 
-                icon = result.chain.at(bytes).ok()
+                self.visited.insert(visited)
             }
+        };
 
-            result
-        })
+        visited.insert(icon_address)
     }
 }
 
+impl<'a> Iterator for IconChain<'a> {
+    type Item = &'a Icon;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let icon = self.next.take()?;
+
+        if !icon.chain.is_null() {
+            self.next = icon
+                .chain
+                .at(self.bytes)
+                .ok()
+                .filter(|&next| self.visit(next));
+        }
+
+        Some(icon)
+    }
+}
+
+fn address(icon: &Icon) -> usize {
+    icon as *const Icon as usize
+}
+
 #[repr(C)]
 #[derive(Debug, FromBytes, KnownLayout, Immutable, Eq, PartialEq)]
 pub struct ImageList {
@@ -145,7 +286,7 @@ pub struct ImageList {
 }
 
 #[repr(C)]
-#[derive(Debug, Copy, Clone, FromBytes, KnownLayout, Immutable, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, FromBytes, KnownLayout, Immutable, IntoBytes, Eq, PartialEq)]
 pub struct Image {
     pub directory_index: U16,
     pub icon_flags: Flags,
@@ -153,7 +294,7 @@ pub struct Image {
 }
 
 #[repr(C)]
-#[derive(Debug, Copy, Clone, Default, FromBytes, Immutable, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Default, FromBytes, Immutable, IntoBytes, Eq, PartialEq)]
 pub struct Flags {
     value: U16,
 }
@@ -183,8 +324,158 @@ impl Flags {
     pub fn has_suffix_png(&self) -> bool {
         (self.value & Self::HAS_SUFFIX_PNG) != 0
     }
+
+    pub fn has_icon_file(&self) -> bool {
+        (self.value & Self::HAS_ICON_FILE) != 0
+    }
+
+    /// Returns `true` if every flag set in `other` is also set in `self`.
+    pub fn contains(&self, other: Flags) -> bool {
+        (self.value & other.value) == other.value
+    }
+
+    /// Returns the file extensions (without leading dot) of the formats the image is available in,
+    /// in order of preference: `svg`, `png`, `xpm`.
+    pub fn suffixes(&self) -> impl Iterator<Item = &'static str> + use<> {
+        let flags = *self;
+
+        [
+            (Self::HAS_SUFFIX_SVG, "svg"),
+            (Self::HAS_SUFFIX_PNG, "png"),
+            (Self::HAS_SUFFIX_XPM, "xpm"),
+        ]
+        .into_iter()
+        .filter(move |&(flag, _)| (flags.value & flag) != 0)
+        .map(|(_, suffix)| suffix)
+    }
+}
+
+impl BitOr for Flags {
+    type Output = Flags;
+
+    fn bitor(self, rhs: Flags) -> Flags {
+        Flags::new(self.value | rhs.value)
+    }
+}
+
+impl BitOr<U16> for Flags {
+    type Output = Flags;
+
+    fn bitor(self, rhs: U16) -> Flags {
+        Flags::new(self.value | rhs)
+    }
+}
+
+impl BitOrAssign for Flags {
+    fn bitor_assign(&mut self, rhs: Flags) {
+        self.value |= rhs.value;
+    }
+}
+
+impl BitOrAssign<U16> for Flags {
+    fn bitor_assign(&mut self, rhs: U16) {
+        self.value |= rhs;
+    }
+}
+
+impl BitAnd for Flags {
+    type Output = Flags;
+
+    fn bitand(self, rhs: Flags) -> Flags {
+        Flags::new(self.value & rhs.value)
+    }
+}
+
+impl BitAnd<U16> for Flags {
+    type Output = Flags;
+
+    fn bitand(self, rhs: U16) -> Flags {
+        Flags::new(self.value & rhs)
+    }
+}
+
+impl Flags {
+    /// Names of the flags, in the order they are displayed
+    const NAMES: [(U16, &'static str); 4] = [
+        (Self::HAS_SUFFIX_SVG, "svg"),
+        (Self::HAS_SUFFIX_PNG, "png"),
+        (Self::HAS_SUFFIX_XPM, "xpm"),
+        (Self::HAS_ICON_FILE, "icon_file"),
+    ];
+}
+
+/// Formats the set flags joined by `|`, e.g. `svg|png`, or `(none)` if no flags are set. Bits
+/// without a name are written last, as a single hexadecimal number, e.g. `svg|0x10`.
+impl Display for Flags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let mut unknown = self.value.get();
+        let mut separator = "";
+        for (flag, name) in Self::NAMES {
+            unknown &= !flag.get();
+            if (self.value & flag) == 0 {
+                continue;
+            }
+
+            write!(f, "{separator}{name}")?;
+            separator = "|";
+        }
+
+        if unknown != 0 {
+            write!(f, "{separator}{unknown:#x}")?;
+        }
+
+        if self.value == 0 {
+            f.write_str("(none)")?;
+        }
+
+        Ok(())
+    }
 }
 
+/// Parses flags in the format they are displayed in: flag names (`svg`, `png`, `xpm` and
+/// `icon_file`) or hexadecimal numbers like `0x10` separated by `|` or `,`, e.g. `svg|png`.
+/// `(none)` and the empty string parse to no flags. Whitespace around names is ignored.
+impl FromStr for Flags {
+    type Err = ParseFlagsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() || s == "(none)" {
+            return Ok(Self::default());
+        }
+
+        let mut value = U16::ZERO;
+        for token in s.split(['|', ',']).map(str::trim) {
+            let flag = match token.strip_prefix("0x") {
+                Some(hex) => u16::from_str_radix(hex, 16)
+                    .map(U16::new)
+                    .map_err(|_| ParseFlagsError)?,
+                None => Self::NAMES
+                    .into_iter()
+                    .find(|&(_, name)| name == token)
+                    .map(|(flag, _)| flag)
+                    .ok_or(ParseFlagsError)?,
+            };
+
+            value |= flag;
+        }
+
+        Ok(Self::new(value))
+    }
+}
+
+/// Error returned when parsing [Flags] from a string containing an unknown flag name.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ParseFlagsError;
+
+impl Display for ParseFlagsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("unknown icon flag name")
+    }
+}
+
+impl Error for ParseFlagsError {}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, FromBytes, KnownLayout, Immutable, Eq, PartialEq)]
 pub struct ImageData {
@@ -204,7 +495,7 @@ pub struct MetaData {
 }
 
 #[repr(C)]
-#[derive(Debug, Copy, Clone, Default, FromBytes, Immutable, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Default, FromBytes, KnownLayout, Immutable, Eq, PartialEq)]
 pub struct EmbeddedRect {
     pub x0: U16,
     pub y0: U16,
@@ -239,3 +530,132 @@ pub struct DisplayName {
     pub display_lang: Offset<U32, CStr>,
     pub display_name: Offset<U32, CStr>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn offset_past_end() {
+        let bytes = [0u8, 0, 0, 1, b'a', 0];
+
+        assert!(Offset::<U32, U32>::new(7).at(&bytes).is_err());
+        assert!(Offset::<U32, CStr>::new(7).str_at(&bytes).is_err());
+        assert!(Offset::<U32, CStr>::new(7).path_at(&bytes).is_none());
+
+        assert!(Offset::<U32, CStr>::new(6).str_at(&bytes).is_err());
+        assert!(Offset::<U32, CStr>::new(6).path_at(&bytes).is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn offset_overruns_end() {
+        let bytes = [0u8, 0, 0, 1, b'a', b'b'];
+
+        assert_eq!(Offset::<U32, U32>::new(0).at(&bytes), Ok(&U32::new(1)));
+        assert!(Offset::<U32, U32>::new(3).at(&bytes).is_err());
+        assert!(Offset::<U32, Icon>::new(0).at(&bytes).is_err());
+
+        // `ab` is not nul-terminated
+        assert!(Offset::<U32, CStr>::new(4).str_at(&bytes).is_err());
+        assert!(Offset::<U32, CStr>::new(4).path_at(&bytes).is_none());
+    }
+
+    #[test]
+    fn length_prefixed() {
+        // Claims 1 image, followed by 2
+        let bytes = [0u8, 0, 0, 1, 0, 1, 0, 2, 0, 0, 0, 0, 0, 3, 0, 4, 0, 0, 0, 0];
+        let image_list = Offset::<U32, ImageList>::new(0).at(&bytes).unwrap();
+
+        assert_eq!(image_list.parsed_elements().len(), 2);
+        assert_eq!(image_list.elements().len(), 1);
+        assert_eq!(image_list.get(0).unwrap().directory_index.get(), 1);
+        assert!(image_list.get(1).is_none());
+        assert!(image_list.get(u32::MAX).is_none());
+
+        // Claims 3 images, but only 2 fit
+        let mut bytes = bytes;
+        bytes[3] = 3;
+        let image_list = Offset::<U32, ImageList>::new(0).at(&bytes).unwrap();
+
+        assert_eq!(image_list.elements().len(), 2);
+        assert_eq!(image_list.get(1).unwrap().directory_index.get(), 3);
+        assert!(image_list.get(2).is_none());
+    }
+
+    #[test]
+    fn flags_display() {
+        assert_eq!(
+            Flags::new(Flags::HAS_SUFFIX_SVG | Flags::HAS_SUFFIX_PNG).to_string(),
+            "svg|png"
+        );
+        assert_eq!(
+            Flags::new(Flags::HAS_ICON_FILE | Flags::HAS_SUFFIX_XPM).to_string(),
+            "xpm|icon_file"
+        );
+        assert_eq!(Flags::default().to_string(), "(none)");
+        assert_eq!(Flags::new(U16::new(0x10)).to_string(), "0x10");
+        assert_eq!(
+            (Flags::new(Flags::HAS_SUFFIX_SVG) | U16::new(0x30)).to_string(),
+            "svg|0x30"
+        );
+    }
+
+    #[test]
+    fn flags_from_str() {
+        assert_eq!(
+            "svg|png".parse::<Flags>(),
+            Ok(Flags::new(Flags::HAS_SUFFIX_SVG | Flags::HAS_SUFFIX_PNG))
+        );
+        assert_eq!(
+            "xpm, icon_file".parse::<Flags>(),
+            Ok(Flags::new(Flags::HAS_SUFFIX_XPM | Flags::HAS_ICON_FILE))
+        );
+        assert_eq!("(none)".parse::<Flags>(), Ok(Flags::default()));
+        assert_eq!("svg|jpg".parse::<Flags>(), Err(ParseFlagsError));
+        assert_eq!("svg||png".parse::<Flags>(), Err(ParseFlagsError));
+        assert_eq!("svg|0xz".parse::<Flags>(), Err(ParseFlagsError));
+        assert_eq!(
+            "svg|0x10".parse::<Flags>(),
+            Ok(Flags::new(Flags::HAS_SUFFIX_SVG | U16::new(0x10)))
+        );
+
+        for bits in (0..64).chain([0x8000, 0xffff]) {
+            let flags = Flags::new(U16::new(bits));
+            assert_eq!(flags.to_string().parse::<Flags>(), Ok(flags));
+        }
+    }
+
+    #[test]
+    fn flags_operators() {
+        let svg = Flags::new(Flags::HAS_SUFFIX_SVG);
+        let png = Flags::new(Flags::HAS_SUFFIX_PNG);
+        let both = Flags::new(Flags::HAS_SUFFIX_SVG | Flags::HAS_SUFFIX_PNG);
+
+        assert_eq!(svg | png, both);
+        assert_eq!(Flags::default() | Flags::HAS_SUFFIX_SVG, svg);
+        assert_eq!(both & png, png);
+        assert_eq!(both & Flags::HAS_SUFFIX_SVG, svg);
+        assert_eq!(svg & png, Flags::default());
+
+        let mut flags = Flags::default();
+        flags |= svg;
+        flags |= Flags::HAS_SUFFIX_PNG;
+        assert_eq!(flags, both);
+
+        assert!(both.contains(svg));
+        assert!(both.contains(both));
+        assert!(both.contains(Flags::default()));
+        assert!(!svg.contains(both));
+    }
+
+    #[test]
+    fn flags_suffixes() {
+        let flags = Flags::new(Flags::HAS_SUFFIX_PNG | Flags::HAS_SUFFIX_SVG);
+        assert_eq!(flags.suffixes().collect::<Vec<_>>(), ["svg", "png"]);
+
+        let flags = Flags::new(Flags::HAS_ICON_FILE);
+        assert_eq!(flags.suffixes().count(), 0);
+    }
+}
@@ -0,0 +1,91 @@
+//! `Serialize` implementations for the high-level types, enabled by the `serde` feature.
+//!
+//! Names and paths are serialized as (lossy) UTF-8 strings.
+
+use crate::{DirectoryInfo, Icon, Image};
+use serde::ser::{Serialize, SerializeSeq, SerializeStruct, Serializer};
+
+impl Serialize for Icon<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut icon = serializer.serialize_struct("Icon", 2)?;
+        icon.serialize_field("name", &self.name.to_string_lossy())?;
+        icon.serialize_field("images", &Images(self))?;
+        icon.end()
+    }
+}
+
+struct Images<'i, 'a>(&'i Icon<'a>);
+
+impl Serialize for Images<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut images = serializer.serialize_seq(None)?;
+        for image in self.0.images() {
+            images.serialize_element(&image)?;
+        }
+        images.end()
+    }
+}
+
+impl Serialize for Image<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut image = serializer.serialize_struct("Image", 3)?;
+        image.serialize_field("directory", &self.directory.to_string_lossy())?;
+        image.serialize_field("suffixes", &self.icon_flags.suffixes().collect::<Vec<_>>())?;
+        image.serialize_field("has_image_data", &self.image_data.is_some())?;
+        image.end()
+    }
+}
+
+impl Serialize for DirectoryInfo<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut info = serializer.serialize_struct("DirectoryInfo", 3)?;
+        info.serialize_field("size", &self.size)?;
+        info.serialize_field("scale", &self.scale)?;
+        info.serialize_field(
+            "context",
+            &self.context.map(|context| context.to_string_lossy()),
+        )?;
+        info.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DirectoryInfo, IconCache};
+    use std::path::Path;
+
+    static SAMPLE_INDEX_FILE: &[u8] = include_bytes!("../assets/icon-theme.cache");
+
+    #[test]
+    fn serialize_icon() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+        let icon = cache.icon("mpv").unwrap();
+
+        let json = serde_json::to_value(icon).unwrap();
+
+        assert_eq!(json["name"], "mpv");
+        assert_eq!(json["images"].as_array().unwrap().len(), 5);
+        assert_eq!(
+            json["images"][0],
+            serde_json::json!({
+                "directory": "scalable/apps",
+                "suffixes": ["svg"],
+                "has_image_data": false,
+            })
+        );
+    }
+
+    #[test]
+    fn serialize_directory_info() {
+        let info = DirectoryInfo::parse(Path::new("16x16@2x/mimetypes"));
+
+        assert_eq!(
+            serde_json::to_value(info).unwrap(),
+            serde_json::json!({
+                "size": 16,
+                "scale": 2,
+                "context": "mimetypes",
+            })
+        );
+    }
+}
@@ -0,0 +1,360 @@
+//! Consistency checks for icon caches from untrusted sources.
+
+use crate::{IconCache, raw};
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::ffi::CStr;
+use core::fmt::{Display, Formatter};
+use core::ops::ControlFlow;
+use zerocopy::network_endian::U32;
+
+/// The kind of structure occupying a [Region] of the cache.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum RegionKind {
+    Header,
+    Hash,
+    DirectoryList,
+    Directory,
+    Icon,
+    IconName,
+    ImageList,
+    ImageData,
+    MetaData,
+}
+
+/// A range of bytes in the cache occupied by a single structure.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Region {
+    pub kind: RegionKind,
+    /// Offset of the first byte of the structure
+    pub offset: usize,
+    /// Length of the structure in bytes
+    pub len: usize,
+}
+
+impl Region {
+    fn end(&self) -> usize {
+        self.offset + self.len
+    }
+}
+
+/// A problem [IconCache::validate] found in a cache, along with where it was found.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ValidationError<'a> {
+    pub problem: Problem,
+    /// The bucket of the hash table whose chain led to the problem, or `None` for problems in the
+    /// directory list
+    pub bucket: Option<u32>,
+    /// The name of the icon the problem belongs to, if it could be read
+    pub icon_name: Option<&'a CStr>,
+}
+
+impl Display for ValidationError<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.problem)?;
+
+        if let Some(bucket) = self.bucket {
+            write!(f, " in bucket {bucket}")?;
+        }
+
+        if let Some(icon_name) = self.icon_name {
+            write!(f, " (icon {icon_name:?})")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ValidationError<'_> {}
+
+/// The kinds of problems [IconCache::validate] checks for.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Problem {
+    /// The structure at `offset` extends past the end of the cache, or `offset` itself does
+    OutOfBounds { kind: RegionKind, offset: usize },
+    /// The image at `offset` refers to a directory that isn't in the directory list
+    InvalidDirectoryIndex { offset: usize, directory_index: u16 },
+    /// The icon at `offset` was already visited: a chain loops back onto itself, or two chains
+    /// share icons
+    ChainLoop { offset: usize },
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Problem::OutOfBounds { kind, offset } => {
+                write!(f, "{kind:?} at offset {offset} is out of bounds")
+            }
+            Problem::InvalidDirectoryIndex {
+                offset,
+                directory_index,
+            } => write!(
+                f,
+                "image at offset {offset} refers to invalid directory index {directory_index}"
+            ),
+            Problem::ChainLoop { offset } => {
+                write!(f, "icon at offset {offset} is reached more than once")
+            }
+        }
+    }
+}
+
+/// Two structures sharing some of the same bytes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Overlap {
+    pub first: Region,
+    pub second: Region,
+}
+
+impl<'a> IconCache<'a> {
+    /// Check that the structures in this cache don't overlap one another.
+    ///
+    /// Reading overlapping structures is memory-safe, but yields nonsensical values: a
+    /// well-formed cache never contains any. Two references to the exact same structure (e.g. a
+    /// directory name shared by two entries) are not considered overlapping.
+    ///
+    /// The header, hash table, directory list, directory names, icons, icon names and image lists
+    /// are checked. Structures that cannot be parsed at all are skipped.
+    pub fn validate_no_overlaps(&self) -> Result<(), Vec<Overlap>> {
+        let mut regions = self.regions();
+        regions.sort();
+        regions.dedup();
+        regions.sort_by_key(|region| region.offset);
+
+        let mut overlaps = vec![];
+        let mut furthest: Option<Region> = None;
+
+        for region in regions {
+            if let Some(previous) = furthest {
+                if region.offset < previous.end() {
+                    overlaps.push(Overlap {
+                        first: previous,
+                        second: region,
+                    });
+                }
+
+                if region.end() <= previous.end() {
+                    continue;
+                }
+            }
+
+            furthest = Some(region);
+        }
+
+        if overlaps.is_empty() {
+            Ok(())
+        } else {
+            Err(overlaps)
+        }
+    }
+
+    /// Check that every offset in this cache resolves to a structure within bounds, and that
+    /// every image refers to a directory in the directory list.
+    ///
+    /// Walks every directory, every bucket and chain of icons, and every icon's name, image list
+    /// and image data. Unlike lookups, which skip anything that fails to parse, this reports the
+    /// first such structure. Pixel data is not checked. To find every problem rather than just
+    /// the first, see [validate_all](Self::validate_all).
+    pub fn validate(&self) -> Result<(), ValidationError<'a>> {
+        match self.walk(&mut ControlFlow::Break) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(error) => Err(error),
+        }
+    }
+
+    /// Check the cache like [validate](Self::validate), but keep going after a problem is found,
+    /// returning every problem in the order they were found.
+    ///
+    /// Structures that can't be read are skipped along with whatever they refer to: an icon
+    /// that is out of bounds ends its chain, and an image list that is out of bounds isn't
+    /// checked any further. A chain that loops is followed only until it does.
+    pub fn validate_all(&self) -> Vec<ValidationError<'a>> {
+        let mut errors = vec![];
+
+        let _ = self.walk::<()>(&mut |error| {
+            errors.push(error);
+            ControlFlow::Continue(())
+        });
+
+        errors
+    }
+
+    /// Walk the cache, passing every problem found to `report`, which decides whether to stop.
+    fn walk<B>(
+        &self,
+        report: &mut impl FnMut(ValidationError<'a>) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        let bytes = self.bytes;
+
+        for directory in self.directory_list.raw_list.directory.iter() {
+            if directory.str_at(bytes).is_err() {
+                report(ValidationError {
+                    problem: out_of_bounds(RegionKind::Directory, directory.offset),
+                    bucket: None,
+                    icon_name: None,
+                })?;
+            }
+        }
+
+        let mut visited = BTreeSet::new();
+        for (bucket, &first) in (0..).zip(self.hash.icon.iter()) {
+            let mut offset = first;
+            let error = |problem, icon_name| ValidationError {
+                problem,
+                bucket: Some(bucket),
+                icon_name,
+            };
+
+            while !offset.is_null() {
+                if !visited.insert(offset.offset.get()) {
+                    let problem = Problem::ChainLoop {
+                        offset: offset.offset.get() as usize,
+                    };
+                    report(error(problem, None))?;
+                    break;
+                }
+
+                let Ok(icon) = offset.at(bytes) else {
+                    report(error(out_of_bounds(RegionKind::Icon, offset.offset), None))?;
+                    break;
+                };
+
+                let icon_name = icon.name.str_at(bytes).ok();
+                if icon_name.is_none() {
+                    let problem = out_of_bounds(RegionKind::IconName, icon.name.offset);
+                    report(error(problem, None))?;
+                }
+
+                self.walk_image_list(icon.image_list, &mut |problem| {
+                    report(error(problem, icon_name))
+                })?;
+
+                offset = icon.chain;
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    fn walk_image_list<B>(
+        &self,
+        offset: raw::Offset<U32, raw::ImageList>,
+        report: &mut impl FnMut(Problem) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        let bytes = self.bytes;
+
+        let Ok(image_list) = offset.at(bytes) else {
+            return report(out_of_bounds(RegionKind::ImageList, offset.offset));
+        };
+        let Some(images) = image_list.images.get(..image_list.n_images.get() as usize) else {
+            return report(out_of_bounds(RegionKind::ImageList, offset.offset));
+        };
+
+        for (idx, image) in images.iter().enumerate() {
+            let directory_index = image.directory_index.get();
+            if directory_index as u32 >= self.directory_list.len() {
+                report(Problem::InvalidDirectoryIndex {
+                    offset: offset.offset.get() as usize
+                        + size_of::<U32>()
+                        + size_of::<raw::Image>() * idx,
+                    directory_index,
+                })?;
+            }
+
+            if image.image_data.offset == 0 {
+                continue;
+            }
+
+            let Ok(image_data) = image.image_data.at(bytes) else {
+                report(out_of_bounds(
+                    RegionKind::ImageData,
+                    image.image_data.offset,
+                ))?;
+                continue;
+            };
+
+            let meta_data = image_data.image_meta_data;
+            if !meta_data.is_null() && meta_data.at(bytes).is_err() {
+                report(out_of_bounds(RegionKind::MetaData, meta_data.offset))?;
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    fn regions(&self) -> Vec<Region> {
+        let bytes = self.bytes;
+        let region = |kind, offset: U32, len| Region {
+            kind,
+            offset: offset.get() as usize,
+            len,
+        };
+        let str_len = |offset: raw::Offset<U32, CStr>| {
+            Some(offset.str_at(bytes).ok()?.to_bytes_with_nul().len())
+        };
+
+        let mut regions = vec![
+            Region {
+                kind: RegionKind::Header,
+                offset: 0,
+                len: size_of::<raw::Header>(),
+            },
+            region(
+                RegionKind::Hash,
+                self.header.hash.offset,
+                size_of::<U32>() * (1 + self.hash.n_buckets.get() as usize),
+            ),
+            region(
+                RegionKind::DirectoryList,
+                self.header.directory_list.offset,
+                size_of::<U32>() * (1 + self.directory_list.len() as usize),
+            ),
+        ];
+
+        for directory in self.directory_list.raw_list.directory.iter() {
+            if let Some(len) = str_len(*directory) {
+                regions.push(region(RegionKind::Directory, directory.offset, len));
+            }
+        }
+
+        let mut visited = BTreeSet::new();
+        for &bucket in self.hash.icon.iter() {
+            let mut offset = bucket;
+
+            // `visited` guards against chains that loop back onto themselves
+            while !offset.is_null() && visited.insert(offset.offset.get()) {
+                let Ok(icon) = offset.at(bytes) else {
+                    break;
+                };
+                regions.push(region(
+                    RegionKind::Icon,
+                    offset.offset,
+                    size_of::<raw::Icon>(),
+                ));
+
+                if let Some(len) = str_len(icon.name) {
+                    regions.push(region(RegionKind::IconName, icon.name.offset, len));
+                }
+
+                if let Ok(image_list) = icon.image_list.at(bytes) {
+                    let len = size_of::<U32>()
+                        + size_of::<raw::Image>() * image_list.n_images.get() as usize;
+                    regions.push(region(RegionKind::ImageList, icon.image_list.offset, len));
+                }
+
+                offset = icon.chain;
+            }
+        }
+
+        regions
+    }
+}
+
+fn out_of_bounds(kind: RegionKind, offset: U32) -> Problem {
+    Problem::OutOfBounds {
+        kind,
+        offset: offset.get() as usize,
+    }
+}
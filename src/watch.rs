@@ -0,0 +1,163 @@
+//! Notice when a cache file is regenerated, and reload it.
+
+use crate::file::OwnedIconCache;
+use notify::event::{AccessKind, EventKind};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::sync::mpsc::{Receiver, channel};
+
+/// Watches the path of an [OwnedIconCache] for changes, e.g. the cache being regenerated by
+/// `gtk-update-icon-cache`.
+///
+/// Create one with [OwnedIconCache::watch]. Watching stops when the `CacheWatcher` is dropped.
+#[derive(Debug)]
+pub struct CacheWatcher {
+    /// Watching stops when this is dropped
+    _watcher: RecommendedWatcher,
+    changes: Receiver<()>,
+}
+
+impl CacheWatcher {
+    /// Returns the channel receiving a message every time the cache file changes.
+    ///
+    /// A single change to the file may be reported more than once.
+    pub fn changes(&self) -> &Receiver<()> {
+        &self.changes
+    }
+
+    /// Returns `true` if the cache file changed since this function was last called.
+    ///
+    /// Does not block.
+    pub fn has_changed(&self) -> bool {
+        self.changes.try_iter().count() > 0
+    }
+}
+
+impl OwnedIconCache {
+    /// Start watching the path this cache was opened from for changes.
+    ///
+    /// `gtk-update-icon-cache` writes a new cache to a temporary file which it then renames over
+    /// the old one, so the path is watched rather than the mapped file itself: the mapped file
+    /// never changes while it is locked.
+    ///
+    /// Returns an error if the cache was not opened from a path (see
+    /// [from_lock](Self::from_lock)) or the path could not be watched.
+    pub fn watch(&self) -> notify::Result<CacheWatcher> {
+        let Some(path) = self.path.as_deref() else {
+            return Err(notify::Error::generic("cache was not opened from a path"));
+        };
+        let (Some(directory), Some(file_name)) = (path.parent(), path.file_name()) else {
+            return Err(notify::Error::path_not_found());
+        };
+        let file_name = file_name.to_os_string();
+
+        let (sender, changes) = channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+
+            if matches!(
+                event.kind,
+                EventKind::Access(AccessKind::Read | AccessKind::Open(_))
+            ) {
+                return;
+            }
+
+            if event
+                .paths
+                .iter()
+                .any(|path| path.file_name() == Some(&file_name))
+            {
+                // The receiver may have been dropped, in which case nobody's listening
+                let _ = sender.send(());
+            }
+        })?;
+
+        // Watch the directory, not the file: a renamed-over file is a different inode
+        let directory = if directory.as_os_str().is_empty() {
+            ".".as_ref()
+        } else {
+            directory
+        };
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+
+        Ok(CacheWatcher {
+            _watcher: watcher,
+            changes,
+        })
+    }
+
+    /// Re-open the cache from its path if `watcher` noticed a change, returning whether it did.
+    ///
+    /// Re-opening takes a new lock, which may block; see [open](Self::open). Caches opened with
+    /// [open_unlocked](Self::open_unlocked) are re-opened without a lock. Since this takes
+    /// `&mut self`, any [IconCache](crate::IconCache) borrowed from the old mapping must be dropped
+    /// first: re-borrow it with [icon_cache](Self::icon_cache) afterward.
+    ///
+    /// If re-opening fails, the old cache is kept and the error is returned.
+    pub fn reload_if_changed(&mut self, watcher: &CacheWatcher) -> std::io::Result<bool> {
+        if !watcher.has_changed() {
+            return Ok(false);
+        }
+
+        let Some(path) = self.path.as_deref() else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "cache was not opened from a path",
+            ));
+        };
+
+        *self = if self.lock.is_some() {
+            Self::open(path)?
+        } else {
+            // SAFETY: the caller guaranteed the file at this path isn't modified when first
+            // opening it unlocked
+            unsafe { Self::open_unlocked(path)? }
+        };
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::write::CacheBuilder;
+    use mktemp::Temp;
+    use std::time::{Duration, Instant};
+
+    static SAMPLE_INDEX_FILE: &[u8] = include_bytes!("../assets/icon-theme.cache");
+
+    #[test]
+    fn reload_replaced_cache() -> Result<(), Box<dyn std::error::Error>> {
+        let root = Temp::new_dir()?;
+        let path = root.join("icon-theme.cache");
+        std::fs::write(&path, SAMPLE_INDEX_FILE)?;
+
+        let mut cache = OwnedIconCache::open(&path)?;
+        let watcher = cache.watch()?;
+        assert!(!cache.reload_if_changed(&watcher)?);
+
+        // Replace the cache the way gtk-update-icon-cache does
+        let mut builder = CacheBuilder::new();
+        builder.add_image("new-icon", "16x16/apps", Default::default());
+        let replacement = root.join(".icon-theme.cache");
+        std::fs::write(&replacement, builder.build())?;
+        std::fs::rename(&replacement, &path)?;
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !cache.reload_if_changed(&watcher)? {
+            assert!(
+                Instant::now() < deadline,
+                "replacing the cache went unnoticed"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let icon_cache = cache.icon_cache()?;
+        assert!(icon_cache.contains("new-icon"));
+        assert!(!icon_cache.contains("mpv"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+//! Produce icon cache files from a list of icons and their images.
+
+use crate::{icon_str_hash, raw};
+use std::collections::HashMap;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use zerocopy::IntoBytes;
+use zerocopy::network_endian::{U16, U32};
+
+/// Bucket counts `gtk-update-icon-cache` picks from (GLib's `g_spaced_primes_closest`).
+const SPACED_PRIMES: [u32; 34] = [
+    11, 19, 37, 73, 109, 163, 251, 367, 557, 823, 1237, 1861, 2777, 4177, 6247, 9371, 14057, 21089,
+    31627, 47431, 71143, 106721, 160073, 240101, 360163, 540217, 810343, 1215497, 1823231, 2734867,
+    4102283, 6153409, 9230113, 13845163,
+];
+
+/// Offset marking the end of an icon chain, or an empty bucket.
+const NULL: u32 = 0xFFFFFFFF;
+
+/// Assembles an icon cache with the same layout `gtk-update-icon-cache` generates.
+///
+/// Directories are numbered in the order they are first added, which `gtk-update-icon-cache` takes
+/// from the theme's `index.theme`. Within a hash bucket, icons are chained in the order they were
+/// first added, and each icon's images are kept in the order they were added.
+///
+/// Embedded image data is not supported: all images are written without it.
+///
+/// ```
+/// use icon_cache::IconCache;
+/// use icon_cache::raw::Flags;
+/// use icon_cache::write::CacheBuilder;
+///
+/// let bytes = CacheBuilder::new()
+///     .add_image("mpv", "scalable/apps", Flags::new(Flags::HAS_SUFFIX_SVG))
+///     .add_image("mpv", "16x16/apps", Flags::new(Flags::HAS_SUFFIX_PNG))
+///     .build();
+///
+/// let cache = IconCache::new_from_bytes(&bytes).unwrap();
+/// assert_eq!(cache.icon("mpv").unwrap().image_list().unwrap().len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CacheBuilder {
+    directories: Vec<PathBuf>,
+    icons: Vec<(Vec<u8>, Vec<raw::Image>)>,
+    /// Index into `icons` by icon name
+    icon_index: HashMap<Vec<u8>, usize>,
+}
+
+impl CacheBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a directory to the directory list, returning its index.
+    ///
+    /// Adding a directory that is already in the list returns its existing index. Only needed to
+    /// control the order of the directory list: [add_image](Self::add_image) adds missing
+    /// directories itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the list already holds the maximum of 65536 directories.
+    pub fn add_directory(&mut self, directory: impl AsRef<Path>) -> u16 {
+        let directory = directory.as_ref();
+
+        if let Some(idx) = self.directories.iter().position(|dir| dir == directory) {
+            return idx as u16;
+        }
+
+        let idx = u16::try_from(self.directories.len()).expect("too many directories");
+        self.directories.push(directory.to_path_buf());
+
+        idx
+    }
+
+    /// Add an image of `icon_name` in `directory`, available in the formats set in `flags`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `icon_name` contains a nul byte, or if `directory` is new and the directory list
+    /// is full (see [add_directory](Self::add_directory)).
+    pub fn add_image(
+        &mut self,
+        icon_name: impl AsRef<[u8]>,
+        directory: impl AsRef<Path>,
+        flags: raw::Flags,
+    ) -> &mut Self {
+        let icon_name = icon_name.as_ref();
+        assert!(!icon_name.contains(&0), "icon name contains a nul byte");
+
+        let image = raw::Image {
+            directory_index: U16::new(self.add_directory(directory)),
+            icon_flags: flags,
+            image_data: raw::Offset::new(0u32),
+        };
+
+        match self.icon_index.get(icon_name) {
+            Some(&idx) => self.icons[idx].1.push(image),
+            None => {
+                self.icon_index.insert(icon_name.to_vec(), self.icons.len());
+                self.icons.push((icon_name.to_vec(), vec![image]));
+            }
+        }
+
+        self
+    }
+
+    /// Serialize the cache.
+    ///
+    /// The header is followed by the hash table, then each bucket's chain of icons (every icon
+    /// directly followed by its name and image list), and finally the directory list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cache would be larger than 4 GiB, which offsets can't address.
+    pub fn build(&self) -> Vec<u8> {
+        let n_buckets = SPACED_PRIMES
+            .into_iter()
+            .find(|&n| n as usize > self.icons.len() / 3)
+            .unwrap_or(SPACED_PRIMES[SPACED_PRIMES.len() - 1]);
+
+        let mut buckets = vec![vec![]; n_buckets as usize];
+        for (name, images) in &self.icons {
+            buckets[(icon_str_hash(name) % n_buckets) as usize].push((name, images));
+        }
+
+        let hash_offset = size_of::<raw::Header>();
+        let mut offset = hash_offset + size_of::<U32>() * (1 + n_buckets as usize);
+
+        // Lay out the icon chains first: the hash table needs to know where each chain starts
+        let mut bucket_offsets = vec![U32::new(NULL); buckets.len()];
+        for (bucket_offset, bucket) in bucket_offsets.iter_mut().zip(&buckets) {
+            if !bucket.is_empty() {
+                *bucket_offset = to_offset(offset);
+            }
+
+            for (name, images) in bucket {
+                offset += icon_len(name, images);
+            }
+        }
+
+        let directory_list_offset = offset;
+
+        let mut bytes = vec![];
+        bytes.extend_from_slice(
+            raw::Header {
+                major_version: U16::new(1),
+                minor_version: U16::new(0),
+                hash: raw::Offset::new(to_offset(hash_offset)),
+                directory_list: raw::Offset::new(to_offset(directory_list_offset)),
+            }
+            .as_bytes(),
+        );
+
+        bytes.extend_from_slice(U32::new(n_buckets).as_bytes());
+        bytes.extend_from_slice(bucket_offsets.as_bytes());
+
+        for bucket in &buckets {
+            for (position, (name, images)) in bucket.iter().enumerate() {
+                let icon_offset = bytes.len();
+                let name_offset = icon_offset + size_of::<raw::Icon>();
+                let image_list_offset = name_offset + padded_len(name);
+
+                let chain = if position + 1 == bucket.len() {
+                    U32::new(NULL)
+                } else {
+                    to_offset(icon_offset + icon_len(name, images))
+                };
+
+                bytes.extend_from_slice(
+                    raw::Icon {
+                        chain: raw::Offset::new(chain),
+                        name: raw::Offset::new(to_offset(name_offset)),
+                        image_list: raw::Offset::new(to_offset(image_list_offset)),
+                    }
+                    .as_bytes(),
+                );
+                push_padded_str(&mut bytes, name);
+
+                bytes.extend_from_slice(U32::new(images.len() as u32).as_bytes());
+                bytes.extend_from_slice(images.as_bytes());
+            }
+        }
+
+        debug_assert_eq!(bytes.len(), directory_list_offset);
+
+        let directories = self
+            .directories
+            .iter()
+            .map(|dir| dir.as_os_str().as_bytes())
+            .collect::<Vec<_>>();
+
+        bytes.extend_from_slice(U32::new(directories.len() as u32).as_bytes());
+
+        let mut offset = bytes.len() + size_of::<U32>() * directories.len();
+        for directory in &directories {
+            bytes.extend_from_slice(to_offset(offset).as_bytes());
+            offset += padded_len(directory);
+        }
+
+        for directory in directories {
+            push_padded_str(&mut bytes, directory);
+        }
+
+        bytes
+    }
+}
+
+/// Length of a nul-terminated string padded to a multiple of 4 bytes.
+fn padded_len(str: &[u8]) -> usize {
+    (str.len() + 1).next_multiple_of(4)
+}
+
+/// Write `str` nul-terminated and padded to a multiple of 4 bytes.
+fn push_padded_str(bytes: &mut Vec<u8>, str: &[u8]) {
+    bytes.extend_from_slice(str);
+    bytes.resize(bytes.len() + padded_len(str) - str.len(), 0);
+}
+
+/// Length of an icon together with its name and image list.
+fn icon_len(name: &[u8], images: &[raw::Image]) -> usize {
+    size_of::<raw::Icon>() + padded_len(name) + size_of::<U32>() + size_of_val(images)
+}
+
+fn to_offset(offset: usize) -> U32 {
+    U32::new(u32::try_from(offset).expect("cache is too large"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IconCache;
+    use crate::raw::Flags;
+
+    static SAMPLE_INDEX_FILE: &[u8] = include_bytes!("../assets/icon-theme.cache");
+
+    #[test]
+    fn round_trip() {
+        let svg = Flags::new(Flags::HAS_SUFFIX_SVG);
+        let png = Flags::new(Flags::HAS_SUFFIX_PNG | Flags::HAS_ICON_FILE);
+
+        let mut builder = CacheBuilder::new();
+        builder
+            .add_image("mpv", "scalable/apps", svg)
+            .add_image("mpv", "16x16/apps", png)
+            .add_image("firefox", "16x16/apps", png)
+            .add_image("a", "scalable/apps", svg);
+        let bytes = builder.build();
+
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+        assert_eq!(cache.version(), (1, 0));
+        assert_eq!(cache.hash.n_buckets.get(), 11);
+        assert_eq!(
+            cache.directory_list.iter().collect::<Vec<_>>(),
+            [Path::new("scalable/apps"), Path::new("16x16/apps")]
+        );
+        assert_eq!(cache.iter().count(), 3);
+
+        let images = |name: &str| {
+            let icon = cache.icon(name).unwrap();
+            (0..icon.image_list().unwrap().len())
+                .filter_map(|idx| icon.image_list().unwrap().image(idx))
+                .map(|image| (image.directory, image.icon_flags))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            images("mpv"),
+            [
+                (Path::new("scalable/apps"), svg),
+                (Path::new("16x16/apps"), png)
+            ]
+        );
+        assert_eq!(images("firefox"), [(Path::new("16x16/apps"), png)]);
+        assert_eq!(images("a"), [(Path::new("scalable/apps"), svg)]);
+
+        assert_eq!(cache.validate_no_overlaps(), Ok(()));
+    }
+
+    #[test]
+    fn rebuild_sample() {
+        let cache = IconCache::new_from_bytes(SAMPLE_INDEX_FILE).unwrap();
+
+        let mut builder = CacheBuilder::new();
+        for directory in cache.directory_list.iter() {
+            builder.add_directory(directory);
+        }
+        for icon in cache.iter() {
+            for image in icon.image_list().unwrap().iter() {
+                builder.add_image(icon.name.to_bytes(), image.directory, image.icon_flags);
+            }
+        }
+
+        assert!(builder.build() == SAMPLE_INDEX_FILE);
+    }
+
+    #[test]
+    fn empty() {
+        let bytes = CacheBuilder::new().build();
+
+        let cache = IconCache::new_from_bytes(&bytes).unwrap();
+        assert_eq!(cache.iter().count(), 0);
+        assert!(cache.directory_list.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn nul_in_name() {
+        CacheBuilder::new().add_image("a\0b", "16x16/apps", Flags::default());
+    }
+}